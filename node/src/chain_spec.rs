@@ -1,9 +1,17 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig, KittiesConfig, KittiesV2Config,
+	AccountId, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig, KittiesConfig, KittiesV2Config,
 	SystemConfig, WASM_BINARY, TemplateModuleConfig, KittiesMyselfConfig
 };
+#[cfg(not(feature = "babe"))]
+use node_template_runtime::AuraConfig;
+#[cfg(feature = "babe")]
+use node_template_runtime::{BabeConfig, BABE_GENESIS_EPOCH_CONFIG};
 use sc_service::ChainType;
-use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+// The key of a block author, Aura by default or BABE with the `babe` feature
+#[cfg(not(feature = "babe"))]
+use sp_consensus_aura::sr25519::AuthorityId as AuthorId;
+#[cfg(feature = "babe")]
+use sp_consensus_babe::AuthorityId as AuthorId;
 use sp_core::{sr25519, Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{IdentifyAccount, Verify};
@@ -30,9 +38,9 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
-	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
+/// Generate an Aura (or BABE) authority key.
+pub fn authority_keys_from_seed(s: &str) -> (AuthorId, GrandpaId) {
+	(get_from_seed::<AuthorId>(s), get_from_seed::<GrandpaId>(s))
 }
 
 pub fn development_config() -> Result<ChainSpec, String> {
@@ -135,7 +143,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	initial_authorities: Vec<(AuthorId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool,
@@ -149,9 +157,15 @@ fn testnet_genesis(
 			// Configure endowed accounts with initial balance of 1 << 60.
 			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
 		},
+		#[cfg(not(feature = "babe"))]
 		aura: AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
 		},
+		#[cfg(feature = "babe")]
+		babe: BabeConfig {
+			authorities: initial_authorities.iter().map(|x| (x.0.clone(), 1)).collect(),
+			epoch_config: Some(BABE_GENESIS_EPOCH_CONFIG),
+		},
 		grandpa: GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		},
@@ -1,5 +1,11 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
 pub use pallet::*;
 
 #[frame_support::pallet]
@@ -14,11 +20,11 @@ pub mod pallet {
 		BoundedVec, Twox64Concat,
 	};
 	use frame_system::pallet_prelude::*;
+	use kitties_primitives::ownership;
 	use scale_info::TypeInfo;
 	use sp_io::hashing::blake2_128;
 
-	#[cfg(feature = "std")]
-	use serde::{Deserialize, Serialize};
+	pub use kitties_primitives::Gender;
 
 	// Our pallet's genesis configuration.
 	#[pallet::genesis_config]
@@ -39,7 +45,7 @@ pub mod pallet {
 		fn build(&self) {
 			// When building a kitty from genesis config, we require the dna and gender to be supplied.
 			for (acct, dna, gender) in &self.kitties {
-				let _ = <Pallet<T>>::mint(acct, Some(dna.clone()), Some(gender.clone()));
+				let _ = <Pallet<T>>::mint(acct, Some(dna.clone()), Some(*gender));
 			}
 		}
 	}
@@ -73,15 +79,6 @@ pub mod pallet {
 		pub trait Balance: AtLeast32BitUnsigned + FullCodec + Copy + Default + Debug + TypeInfo + MaxEncodedLen { }
 	*/
 
-	// Enum Gender
-	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
-	#[scale_info(skip_type_params(T))]
-	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-	pub enum Gender {
-		Female,
-		Male,
-	}
-
 	//struct for holding Kitty information
 	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
 	#[scale_info(skip_type_params(T))]
@@ -123,6 +120,8 @@ pub mod pallet {
 		Transferred(T::AccountId, T::AccountId, T::Hash),
 		/// A Kitty was sucessfully bought. \[buyer, seller, kitty_id, bid_price\]
 		Bought(T::AccountId, T::AccountId, T::Hash, BalanceOf<T>),
+		/// A Kitty was sucessfully burned. \[owner, kitty_id\]
+		Burned(T::AccountId, T::Hash),
 	}
 
 	// Error: định nghĩa các error sẽ trả về, giúp error tường minh hơn
@@ -146,6 +145,8 @@ pub mod pallet {
 		KittyBidPriceTooLow,
 		/// Ensures that an account has enough funds to purchase a Kitty.
 		NotEnoughBalance,
+		/// Handles arithemtic underflow when decrementing the Kitty counter.
+		KittyCountUnderFlow,
 	}
 
 	// Hook: định nghĩa các action sẽ diễn ra tại một thời điểm nhất định trong quá trình mint block, ex init block, finalize block, runtime upgrade
@@ -263,6 +264,29 @@ pub mod pallet {
 			Self::mint(&who, Some(new_dna), None)?;
 			Ok(())
 		}
+
+		#[pallet::weight(100)]
+		pub fn burn(origin: OriginFor<T>, kitty_id: T::Hash) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and is called by the kitty owner
+			ensure!(Self::is_kitty_owner(&kitty_id, &owner)?, <Error<T>>::NotKittyOwner);
+
+			Self::burn_kitty(&kitty_id)?;
+			Self::deposit_event(Event::Burned(owner, kitty_id));
+			Ok(())
+		}
+
+		// Root can burn any kitty, for moderation
+		#[pallet::weight(100)]
+		pub fn force_burn(origin: OriginFor<T>, kitty_id: T::Hash) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let owner = Self::kitties(&kitty_id).ok_or(<Error<T>>::KittyNotExist)?.owner;
+			Self::burn_kitty(&kitty_id)?;
+			Self::deposit_event(Event::Burned(owner, kitty_id));
+			Ok(())
+		}
 	}
 
 	// Helper: Định nghĩa các helper func để sử dụng trong pallet
@@ -313,7 +337,7 @@ pub mod pallet {
 				Self::kitty_count().checked_add(1).ok_or(<Error<T>>::KittyCountOverFlow)?;
 
 			// Performs this operation first because as it may fail
-			<KittiesOwned<T>>::try_mutate(&owner, |kitty_vec| kitty_vec.try_push(kitty_id))
+			<KittiesOwned<T>>::try_mutate(&owner, |kitty_vec| ownership::insert(kitty_vec, kitty_id))
 				.map_err(|_| <Error<T>>::ExceedMaxKittyOwned)?;
 
 			<Kitties<T>>::insert(kitty_id, kitty);
@@ -335,8 +359,7 @@ pub mod pallet {
 
 			// Remove `kitty_id` from the KittyOwned vector of `prev_kitty_owner`
 			<KittiesOwned<T>>::try_mutate(&prev_owner, |owned| {
-				if let Some(ind) = owned.iter().position(|&id| id == *kitty_id) {
-					owned.swap_remove(ind);
+				if ownership::remove(owned, kitty_id) {
 					return Ok(());
 				}
 				Err(())
@@ -351,10 +374,31 @@ pub mod pallet {
 
 			<Kitties<T>>::insert(kitty_id, kitty);
 
-			<KittiesOwned<T>>::try_mutate(to, |vec| vec.try_push(*kitty_id))
+			<KittiesOwned<T>>::try_mutate(to, |vec| ownership::insert(vec, *kitty_id))
 				.map_err(|_| <Error<T>>::ExceedMaxKittyOwned)?;
 
 			Ok(())
 		}
+
+		fn burn_kitty(kitty_id: &T::Hash) -> Result<(), Error<T>> {
+			let kitty = Self::kitties(kitty_id).ok_or(<Error<T>>::KittyNotExist)?;
+
+			// Performs this operation first as it may fail
+			let new_count =
+				Self::kitty_count().checked_sub(1).ok_or(<Error<T>>::KittyCountUnderFlow)?;
+
+			// Remove `kitty_id` from the KittyOwned vector of the owner
+			<KittiesOwned<T>>::try_mutate(&kitty.owner, |owned| {
+				if ownership::remove(owned, kitty_id) {
+					return Ok(());
+				}
+				Err(())
+			})
+			.map_err(|_| <Error<T>>::KittyNotExist)?;
+
+			<Kitties<T>>::remove(kitty_id);
+			<KittyCount<T>>::put(new_count);
+			Ok(())
+		}
 	}
 }
@@ -0,0 +1,62 @@
+use crate::{mock::*, Error, *};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::H256;
+use sp_runtime::DispatchError;
+
+#[test]
+fn test_burn_work() {
+	new_test_ext().execute_with(|| {
+		let kitty_id = <KittiesOwned<Test>>::get(ALICE)[0];
+		assert_ok!(KittiesModule::burn(RuntimeOrigin::signed(ALICE), kitty_id));
+		System::assert_last_event(RuntimeEvent::KittiesModule(Event::Burned(ALICE, kitty_id)));
+
+		// check kitty is removed
+		assert_eq!(<Kitties<Test>>::get(kitty_id), None);
+		assert!(<KittiesOwned<Test>>::get(ALICE).is_empty());
+		assert_eq!(<KittyCount<Test>>::get(), 0);
+	})
+}
+
+#[test]
+fn test_burn_fail() {
+	new_test_ext().execute_with(|| {
+		let kitty_id = <KittiesOwned<Test>>::get(ALICE)[0];
+		assert_noop!(
+			KittiesModule::burn(RuntimeOrigin::signed(BOB), kitty_id),
+			Error::<Test>::NotKittyOwner
+		);
+		assert_noop!(
+			KittiesModule::burn(RuntimeOrigin::signed(ALICE), H256::zero()),
+			Error::<Test>::KittyNotExist
+		);
+	})
+}
+
+#[test]
+fn test_force_burn_work() {
+	new_test_ext().execute_with(|| {
+		let kitty_id = <KittiesOwned<Test>>::get(ALICE)[0];
+		assert_ok!(KittiesModule::force_burn(RuntimeOrigin::root(), kitty_id));
+		System::assert_last_event(RuntimeEvent::KittiesModule(Event::Burned(ALICE, kitty_id)));
+
+		// check kitty is removed from its owner
+		assert_eq!(<Kitties<Test>>::get(kitty_id), None);
+		assert!(<KittiesOwned<Test>>::get(ALICE).is_empty());
+		assert_eq!(<KittyCount<Test>>::get(), 0);
+	})
+}
+
+#[test]
+fn test_force_burn_fail() {
+	new_test_ext().execute_with(|| {
+		let kitty_id = <KittiesOwned<Test>>::get(ALICE)[0];
+
+		// check only root can force burn, even the owner can't
+		assert_noop!(KittiesModule::force_burn(RuntimeOrigin::signed(ALICE), kitty_id), DispatchError::BadOrigin);
+		assert_noop!(KittiesModule::force_burn(RuntimeOrigin::signed(BOB), kitty_id), DispatchError::BadOrigin);
+		assert_noop!(
+			KittiesModule::force_burn(RuntimeOrigin::root(), H256::zero()),
+			Error::<Test>::KittyNotExist
+		);
+	})
+}
@@ -0,0 +1,23 @@
+//! Runtime API definition for the kitties myself pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+pub use pallet_kitties_myself::{KittyAge, LifeStage};
+
+sp_api::decl_runtime_apis! {
+	/// Queries on the age of kitties.
+	pub trait KittiesAgeApi<Hash, BlockNumber, Moment, Balance>
+	where
+		Hash: Codec,
+		BlockNumber: Codec,
+		Moment: Codec,
+		Balance: Codec,
+	{
+		/// The age of a kitty in blocks and milliseconds, with its life stage.
+		fn age(kitty: Hash) -> Option<KittyAge<BlockNumber, Moment>>;
+		/// The price suggested for a kitty at its age, None for retired kitties.
+		fn suggested_price(kitty: Hash) -> Option<Balance>;
+	}
+}
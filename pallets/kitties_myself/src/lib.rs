@@ -8,21 +8,30 @@ mod tests;
 
 pub use pallet::*;
 
+pub mod migrations;
+
 #[frame_support::pallet]
 pub mod pallet {
     use frame_support::{
         pallet_prelude::*,
-        traits::{Time, Randomness, Currency},
-        sp_runtime::traits::{Hash, Zero},
+        traits::{Time, Randomness, Currency, ExistenceRequirement, StorageVersion},
+        sp_runtime::traits::{Hash, Saturating, Zero},
         BoundedVec
     };
     use frame_system::pallet_prelude::*;
+    use kitties_primitives::ownership;
     use scale_info::TypeInfo;
-    use sp_runtime::ArithmeticError;
+    use sp_runtime::{ArithmeticError, Perbill, SaturatedConversion};
+
+    pub use kitties_primitives::Gender;
+
+    // Version của storage, tăng lên mỗi khi cần migration
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
     // Khai báo 1 struct pallet placeholder để có thể sử dụng trong runtime
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -35,28 +44,58 @@ pub mod pallet {
         type Currency: Currency<Self::AccountId>;
         #[pallet::constant]
         type MaxKittiesOwned: Get<u32>;
+        // Số lần thử tạo kitty_dna chưa tồn tại trước khi báo lỗi DnaCollision
+        #[pallet::constant]
+        type MaxDnaAttempts: Get<u32>;
+        // Tuổi (ms) kitty còn là kitten, chưa thể breed
+        #[pallet::constant]
+        type KittenAge: Get<MomentOf<Self>>;
+        // Tuổi (ms) kitty thành elder, không thể breed nữa nhưng có thể retire
+        #[pallet::constant]
+        type ElderAge: Get<MomentOf<Self>>;
+        // Thời gian (ms) trung bình mỗi block, để quy đổi tuổi ra số block
+        #[pallet::constant]
+        type ExpectedBlockTime: Get<MomentOf<Self>>;
+        // Giá gợi ý cho kitty adult
+        #[pallet::constant]
+        type BasePrice: Get<BalanceOf<Self>>;
     }
 
     // define moment type
-    type MomentOf<T> = <<T as Config>::Time as Time>::Moment;
-    type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
-
-    #[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
-    pub enum Gender {
-        Female,
-        Male
-    }
+    pub type MomentOf<T> = <<T as Config>::Time as Time>::Moment;
+    pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
     #[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
     #[scale_info(skip_type_params(T))]
     pub struct Kitty<T: Config> {
         pub dna: T::Hash,
         pub owner: T::AccountId,
-        pub price: BalanceOf<T>,
+        pub price: Option<BalanceOf<T>>, // None khi chưa đặt giá
+        pub for_sale: bool,
         pub gender: Gender,
         pub created_date: MomentOf<T>
     }
 
+    // Giai đoạn của kitty theo tuổi
+    #[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub enum LifeStage {
+        // Younger than KittenAge, can't breed
+        Kitten,
+        // Can breed
+        Adult,
+        // At least ElderAge, can't breed but can retire
+        Elder
+    }
+
+    // Tuổi của kitty, trả về qua runtime API
+    #[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+    pub struct KittyAge<BlockNumber, Moment> {
+        pub blocks: BlockNumber, // ước tính từ ExpectedBlockTime
+        pub millis: Moment,
+        pub stage: LifeStage,
+        pub retired: bool
+    }
+
     #[pallet::storage]
     #[pallet::getter(fn kitties_total)]
     // Keep track of the number of Kitties existence
@@ -72,6 +111,11 @@ pub mod pallet {
     // Mapping kitty_owner => Vec<kitty_dna>
     pub type KittiesOwned<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<T::Hash, T::MaxKittiesOwned>, ValueQuery,>;
 
+    #[pallet::storage]
+    #[pallet::getter(fn retired_kitties)]
+    // Mapping kitty_dna => thời điểm retire
+    pub type RetiredKitties<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, MomentOf<T>>;
+
     // Định nghĩa các event để emit khi các action thành công
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -79,7 +123,17 @@ pub mod pallet {
         // Emit when kitty minted
         Minted {owner: T::AccountId, kitty_dna: T::Hash},
         // Emit when transfer kitty
-        Transferred {from: T::AccountId, to: T::AccountId, kitty_dna: T::Hash}
+        Transferred {from: T::AccountId, to: T::AccountId, kitty_dna: T::Hash},
+        // Emit when owner set price, None when kitty no longer for sale
+        PriceSet {kitty_dna: T::Hash, price: Option<BalanceOf<T>>},
+        // Emit when kitty sold
+        Sold {seller: T::AccountId, buyer: T::AccountId, kitty_dna: T::Hash, price: BalanceOf<T>},
+        // Emit when two adults bred a kitty, which is also Minted
+        Bred {owner: T::AccountId, kitty_dna: T::Hash, parents: (T::Hash, T::Hash)},
+        // Emit when an elder kitty retired
+        Retired {kitty_dna: T::Hash},
+        // Emit when kitty burned
+        Burned {owner: T::AccountId, kitty_dna: T::Hash}
     }
 
     // Định nghĩa các error để emit khi lỗi xảy ra
@@ -94,7 +148,21 @@ pub mod pallet {
         // Transfer to myself
         NotTransferToSelf,
         // limit kitty owned
-        TooManyOwned
+        TooManyOwned,
+        // Buy kitty not for sale
+        NotForSale,
+        // Buy kitty with limit price lower than price
+        BidPriceTooLow,
+        // Every kitty_dna tried within MaxDnaAttempts exists
+        DnaCollision,
+        // Breed kitties of the same gender or a kitty with itself
+        CantBreed,
+        // Breed a kitten or an elder
+        NotAdult,
+        // Retire a kitty that isn't elder
+        NotElder,
+        // Sell or breed a retired kitty
+        KittyRetired
     }
 
     #[pallet::genesis_config]
@@ -115,11 +183,10 @@ pub mod pallet {
 	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
 		fn build(&self){
 			for (index, account) in self.kitties_myself.iter().enumerate(){
-                let price: BalanceOf<T> = Zero::zero();
                 let moment: MomentOf<T> = Zero::zero();
                 let dna = T::Hashing::hash(&[index as u8]);
                 let gender = if index%2 == 0 {Gender::Male} else {Gender::Female};
-				assert!(<Pallet<T>>::create_kitty(account, price, dna, gender, moment).is_ok());
+				assert!(<Pallet<T>>::create_kitty(account, None, dna, gender, moment).is_ok());
 			}
 		}
 	}
@@ -130,13 +197,14 @@ pub mod pallet {
     // Định nghĩa các fn có thể gọi từ bên ngoài vào run time (extrinsic)
     #[pallet::call]
     impl<T: Config> Pallet<T>{
+        // Mint a kitty, which is for sale when minted with a price
         #[pallet::weight(100)]
-        pub fn mint_kitty(origin: OriginFor<T>, price: BalanceOf<T>) -> DispatchResult {
+        pub fn mint_kitty(origin: OriginFor<T>, price: Option<BalanceOf<T>>) -> DispatchResult {
             // Make sure the caller is from a signed origin
             let sender = ensure_signed(origin)?;
 
             // Determine gender depend on dna.len()
-            let (dna, gender) = Self::gen_dna_gender();
+            let (dna, gender) = Self::gen_dna_gender(&sender, None)?;
             let created_date = T::Time::now();
 
             Self::create_kitty(&sender, price, dna, gender, created_date)
@@ -152,52 +220,267 @@ pub mod pallet {
             ensure!(kitty != None, <Error<T>>::KittyNotExisted);
 
             // Make sure sender is owner
-            let mut kitty = kitty.unwrap();
+            let kitty = kitty.unwrap();
             ensure!(kitty.owner == sender, <Error<T>>::NotOwner);
             ensure!(kitty.owner != to, <Error<T>>::NotTransferToSelf);
 
-            // remove kitty_dna from account
-            let mut from_owned = Self::kitties_owned(&sender);
-            if let Some(index) = from_owned.iter().position(|dna| kitty_dna == *dna) {
-                from_owned.swap_remove(index);
-            } else {
-                return Err(Error::<T>::NotOwner.into());
-            };
+            Self::do_transfer(kitty_dna, kitty, &to)
+        }
 
-            <KittiesOwned<T>>::try_mutate(&to, |list_kitty| {
-                list_kitty.try_push(kitty_dna.clone())
-            }).map_err(|_| <Error<T>>::TooManyOwned)?;
+        // Set the price of a kitty, None takes it off sale
+        #[pallet::weight(100)]
+        pub fn set_price(origin: OriginFor<T>, kitty_dna: T::Hash, new_price: Option<BalanceOf<T>>) -> DispatchResult {
+            // Make sure the caller is from a signed origin
+            let sender = ensure_signed(origin)?;
 
-             // change owner of kitty
-            kitty.owner = to.clone();
-            <Kitties<T>>::insert(kitty_dna.clone(), kitty);
-            <KittiesOwned<T>>::insert(sender.clone(), from_owned);
+            // Make sure kitty exists and sender is owner
+            let mut kitty = Self::kitties(&kitty_dna).ok_or(<Error<T>>::KittyNotExisted)?;
+            ensure!(kitty.owner == sender, <Error<T>>::NotOwner);
+            ensure!(!<RetiredKitties<T>>::contains_key(&kitty_dna), <Error<T>>::KittyRetired);
+
+            kitty.price = new_price;
+            kitty.for_sale = new_price.is_some();
+            <Kitties<T>>::insert(&kitty_dna, kitty);
 
             // emit event
-            Self::deposit_event(Event::Transferred{from: sender, to, kitty_dna});
+            Self::deposit_event(Event::PriceSet{kitty_dna, price: new_price});
             Ok(())
         }
+
+        // Buy a kitty for sale at its price. `limit_price` is the most the buyer accepts to pay, so
+        // the owner can't raise the price in front of the purchase
+        #[pallet::weight(100)]
+        pub fn buy_kitty(origin: OriginFor<T>, kitty_dna: T::Hash, limit_price: BalanceOf<T>) -> DispatchResult {
+            // Make sure the caller is from a signed origin
+            let buyer = ensure_signed(origin)?;
+
+            // Make sure kitty exists and buyer is not owner
+            let kitty = Self::kitties(&kitty_dna).ok_or(<Error<T>>::KittyNotExisted)?;
+            ensure!(kitty.owner != buyer, <Error<T>>::NotTransferToSelf);
+
+            // Make sure kitty for sale within the limit price
+            let price = kitty.price.filter(|_| kitty.for_sale).ok_or(<Error<T>>::NotForSale)?;
+            ensure!(limit_price >= price, <Error<T>>::BidPriceTooLow);
+
+            // Pay the owner, buyer account must stay alive
+            let seller = kitty.owner.clone();
+            T::Currency::transfer(&buyer, &seller, price, ExistenceRequirement::KeepAlive)?;
+
+            Self::do_transfer(kitty_dna, kitty, &buyer)?;
+
+            // emit event
+            Self::deposit_event(Event::Sold{seller, buyer, kitty_dna, price});
+            Ok(())
+        }
+
+        // Breed two adult kitties of the sender. Each bit of the child kitty_dna comes from one of
+        // the parents, picked at random
+        #[pallet::weight(100)]
+        pub fn breed_kitty(origin: OriginFor<T>, parent_1: T::Hash, parent_2: T::Hash) -> DispatchResult {
+            // Make sure the caller is from a signed origin
+            let sender = ensure_signed(origin)?;
+
+            // Make sure both parents exist and sender is owner
+            let kitty_1 = Self::kitties(&parent_1).ok_or(<Error<T>>::KittyNotExisted)?;
+            let kitty_2 = Self::kitties(&parent_2).ok_or(<Error<T>>::KittyNotExisted)?;
+            ensure!(kitty_1.owner == sender && kitty_2.owner == sender, <Error<T>>::NotOwner);
+            ensure!(kitty_1.gender != kitty_2.gender, <Error<T>>::CantBreed);
+
+            // Only adults that are not retired can breed
+            for (dna, kitty) in [(&parent_1, &kitty_1), (&parent_2, &kitty_2)] {
+                ensure!(!<RetiredKitties<T>>::contains_key(dna), <Error<T>>::KittyRetired);
+                ensure!(Self::life_stage(Self::age_millis(kitty)) == LifeStage::Adult, <Error<T>>::NotAdult);
+            }
+
+            let (dna, gender) = Self::gen_dna_gender(&sender, Some((parent_1, parent_2)))?;
+            Self::create_kitty(&sender, None, dna, gender, T::Time::now())?;
+
+            // emit event
+            Self::deposit_event(Event::Bred{owner: sender, kitty_dna: dna, parents: (parent_1, parent_2)});
+            Ok(())
+        }
+
+        // Retire an elder kitty, for good. It is taken off sale and can't breed or be priced again
+        #[pallet::weight(100)]
+        pub fn retire_kitty(origin: OriginFor<T>, kitty_dna: T::Hash) -> DispatchResult {
+            // Make sure the caller is from a signed origin
+            let sender = ensure_signed(origin)?;
+
+            // Make sure kitty exists and sender is owner
+            let mut kitty = Self::kitties(&kitty_dna).ok_or(<Error<T>>::KittyNotExisted)?;
+            ensure!(kitty.owner == sender, <Error<T>>::NotOwner);
+            ensure!(!<RetiredKitties<T>>::contains_key(&kitty_dna), <Error<T>>::KittyRetired);
+            ensure!(Self::life_stage(Self::age_millis(&kitty)) == LifeStage::Elder, <Error<T>>::NotElder);
+
+            kitty.price = None;
+            kitty.for_sale = false;
+            <Kitties<T>>::insert(&kitty_dna, kitty);
+            <RetiredKitties<T>>::insert(&kitty_dna, T::Time::now());
+
+            // emit event
+            Self::deposit_event(Event::Retired{kitty_dna});
+            Ok(())
+        }
+
+        // Burn a kitty of the sender
+        #[pallet::weight(100)]
+        pub fn burn(origin: OriginFor<T>, kitty_dna: T::Hash) -> DispatchResult {
+            // Make sure the caller is from a signed origin
+            let sender = ensure_signed(origin)?;
+
+            // Make sure kitty exists and sender is owner
+            let kitty = Self::kitties(&kitty_dna).ok_or(<Error<T>>::KittyNotExisted)?;
+            ensure!(kitty.owner == sender, <Error<T>>::NotOwner);
+
+            Self::do_burn(kitty_dna, kitty)
+        }
+
+        // Burn any kitty, root only for moderation
+        #[pallet::weight(100)]
+        pub fn force_burn(origin: OriginFor<T>, kitty_dna: T::Hash) -> DispatchResult {
+            ensure_root(origin)?;
+
+            let kitty = Self::kitties(&kitty_dna).ok_or(<Error<T>>::KittyNotExisted)?;
+            Self::do_burn(kitty_dna, kitty)
+        }
     }
 
     // Helper func là các hàm hỗ trợ xử lý các logic cũng như tránh lặp code, giúp bảo mật code
     impl<T: Config> Pallet<T>{
-        fn gen_dna_gender() -> (T::Hash, Gender) {
-            // Get random kitty_dna make sure it not exists
-            let mut random = T::KittyDnaRandom::random(&b"dna"[..]).0;
-            while Self::kitties(&random) != None {
-                random = T::KittyDnaRandom::random(&b"dna"[..]).0;
+        // Tuổi và giai đoạn của kitty, None khi kitty không tồn tại
+        pub fn kitty_age(kitty_dna: T::Hash) -> Option<KittyAge<T::BlockNumber, MomentOf<T>>> {
+            let kitty = Self::kitties(&kitty_dna)?;
+            let millis = Self::age_millis(&kitty);
+
+            let block_time = T::ExpectedBlockTime::get();
+            let blocks = if block_time.is_zero() { Zero::zero() } else { millis / block_time };
+
+            Some(KittyAge {
+                blocks: blocks.saturated_into::<u64>().saturated_into(),
+                millis,
+                stage: Self::life_stage(millis),
+                retired: <RetiredKitties<T>>::contains_key(&kitty_dna),
+            })
+        }
+
+        // Giá gợi ý theo giai đoạn: kitten 50%, adult 100%, elder 25% của BasePrice. Kitty đã
+        // retire không bán được nên không có giá gợi ý
+        pub fn suggested_price(kitty_dna: T::Hash) -> Option<BalanceOf<T>> {
+            let age = Self::kitty_age(kitty_dna)?;
+            if age.retired {
+                return None;
+            }
+
+            let share = match age.stage {
+                LifeStage::Kitten => Perbill::from_percent(50),
+                LifeStage::Adult => Perbill::from_percent(100),
+                LifeStage::Elder => Perbill::from_percent(25),
             };
-            let gender = if random.encode()[0] % 2 == 0 {Gender::Male} else {Gender::Female};
-            
-            return (random, gender)
+            Some(share.mul_floor(T::BasePrice::get()))
+        }
+
+        // Giai đoạn của kitty ở tuổi `age` (ms)
+        pub fn life_stage(age: MomentOf<T>) -> LifeStage {
+            if age < T::KittenAge::get() {
+                LifeStage::Kitten
+            } else if age < T::ElderAge::get() {
+                LifeStage::Adult
+            } else {
+                LifeStage::Elder
+            }
+        }
+
+        // Tuổi (ms) của kitty tính từ created_date
+        fn age_millis(kitty: &Kitty<T>) -> MomentOf<T> {
+            T::Time::now().saturating_sub(kitty.created_date)
+        }
+
+        // Move kitty to new owner, new owner has to set price again to sell it
+        fn do_transfer(kitty_dna: T::Hash, mut kitty: Kitty<T>, to: &T::AccountId) -> DispatchResult {
+            let from = kitty.owner.clone();
+
+            // remove kitty_dna from account
+            let mut from_owned = Self::kitties_owned(&from);
+            ensure!(ownership::remove(&mut from_owned, &kitty_dna), <Error<T>>::NotOwner);
+
+            <KittiesOwned<T>>::try_mutate(to, |list_kitty| {
+                ownership::insert(list_kitty, kitty_dna.clone())
+            }).map_err(|_| <Error<T>>::TooManyOwned)?;
+
+            // change owner of kitty
+            kitty.owner = to.clone();
+            kitty.price = None;
+            kitty.for_sale = false;
+            <Kitties<T>>::insert(kitty_dna.clone(), kitty);
+            <KittiesOwned<T>>::insert(from.clone(), from_owned);
+
+            // emit event
+            Self::deposit_event(Event::Transferred{from, to: to.clone(), kitty_dna});
+            Ok(())
+        }
+
+        // Xoá kitty khỏi storage và giảm KittiesTotal
+        fn do_burn(kitty_dna: T::Hash, kitty: Kitty<T>) -> DispatchResult {
+            // Check kitty total underflow, if not -1
+            let new_kitty_total = Self::kitties_total().checked_sub(1).ok_or(ArithmeticError::Underflow)?;
+
+            // remove kitty_dna from account
+            let mut owned = Self::kitties_owned(&kitty.owner);
+            ensure!(ownership::remove(&mut owned, &kitty_dna), <Error<T>>::NotOwner);
+
+            <KittiesOwned<T>>::insert(kitty.owner.clone(), owned);
+            <Kitties<T>>::remove(&kitty_dna);
+            <RetiredKitties<T>>::remove(&kitty_dna);
+            <KittiesTotal<T>>::put(new_kitty_total);
+
+            // emit event
+            Self::deposit_event(Event::Burned{owner: kitty.owner, kitty_dna});
+            Ok(())
+        }
+
+        // Get random kitty_dna make sure it not exists. The randomness is the same within a block,
+        // so the seed also mixes the extrinsic index, the minter and its nonce, and a bounded number
+        // of attempts is tried. A bred kitty_dna mixes the parents as the random one picks
+        fn gen_dna_gender(account: &T::AccountId, parents: Option<(T::Hash, T::Hash)>) -> Result<(T::Hash, Gender), DispatchError> {
+            let random = T::KittyDnaRandom::random(&b"dna"[..]).0;
+            let extrinsic_index = frame_system::Pallet::<T>::extrinsic_index().unwrap_or_default();
+            let nonce = frame_system::Pallet::<T>::account_nonce(account);
+
+            for attempt in 0..T::MaxDnaAttempts::get() {
+                let mut dna = Self::dna_of(random, extrinsic_index, account, nonce, attempt);
+                if let Some((parent_1, parent_2)) = parents {
+                    dna = Self::mix_dna(&dna, &parent_1, &parent_2);
+                }
+                if !<Kitties<T>>::contains_key(&dna) {
+                    let gender = if dna.encode()[0] % 2 == 0 {Gender::Male} else {Gender::Female};
+                    return Ok((dna, gender));
+                }
+            }
+
+            Err(<Error<T>>::DnaCollision.into())
+        }
+
+        // kitty_dna of a child, each bit from parent_1 where it is set in selector, else from parent_2
+        pub(crate) fn mix_dna(selector: &T::Hash, parent_1: &T::Hash, parent_2: &T::Hash) -> T::Hash {
+            let mut child = *parent_1;
+            for ((bits, select), other) in child.as_mut().iter_mut().zip(selector.as_ref()).zip(parent_2.as_ref()) {
+                *bits = (*bits & select) | (other & !select);
+            }
+            child
+        }
+
+        // kitty_dna derived from a seed
+        pub(crate) fn dna_of(random: T::Hash, extrinsic_index: u32, account: &T::AccountId, nonce: T::Index, attempt: u32) -> T::Hash {
+            T::Hashing::hash_of(&(random, extrinsic_index, account, nonce, attempt))
         }
 
-        fn create_kitty(account: &T::AccountId, price: BalanceOf<T>, dna: T::Hash, gender: Gender, created_date: MomentOf<T>) -> DispatchResult {
+        fn create_kitty(account: &T::AccountId, price: Option<BalanceOf<T>>, dna: T::Hash, gender: Gender, created_date: MomentOf<T>) -> DispatchResult {
             // Create kitty
             let kitty = Kitty::<T> {
                 dna: dna.clone(),
                 owner: account.clone(),
                 price,
+                for_sale: price.is_some(),
                 gender,
                 created_date
             };
@@ -208,7 +491,7 @@ pub mod pallet {
 
             // Update kittiesowned for owner
             <KittiesOwned<T>>::try_mutate(&account, |list_kitty| {
-                list_kitty.try_push(dna.clone())
+                ownership::insert(list_kitty, dna.clone())
             }).map_err(|_| <Error<T>>::TooManyOwned)?;
 
             // update kitty total
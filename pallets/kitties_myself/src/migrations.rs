@@ -0,0 +1,93 @@
+// Storage migrations của pallet, mỗi version nằm trong 1 module riêng và được gắn vào `Executive`
+// của runtime.
+
+use super::*;
+use frame_support::{
+    pallet_prelude::*,
+    traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+};
+use sp_runtime::traits::Zero;
+
+#[cfg(feature = "try-runtime")]
+use frame_support::sp_std::vec::Vec;
+
+pub mod v1 {
+    use super::*;
+
+    // Kitty layout before the sale state, a zero price meant the kitty was free
+    #[derive(Encode, Decode)]
+    pub struct OldKitty<T: Config> {
+        pub dna: T::Hash,
+        pub owner: T::AccountId,
+        pub price: BalanceOf<T>,
+        pub gender: Gender,
+        pub created_date: MomentOf<T>,
+    }
+
+    // Turns the mandatory price into an optional one plus a sale state. A zero price becomes no
+    // price, and kitties with a price are for sale as their owner asked at mint time.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() >= 1 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Kitties::<T>::translate::<OldKitty<T>, _>(|_dna, old| {
+                translated += 1;
+                let price = if old.price.is_zero() { None } else { Some(old.price) };
+                Some(Kitty::<T> {
+                    dna: old.dna,
+                    owner: old.owner,
+                    price,
+                    for_sale: price.is_some(),
+                    gender: old.gender,
+                    created_date: old.created_date,
+                })
+            });
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+            ensure!(Pallet::<T>::on_chain_storage_version() == 0, "Already migrated");
+
+            // Count the kitties and those with a price in the old layout
+            let mut count = 0u64;
+            let mut priced = 0u64;
+            for dna in Kitties::<T>::iter_keys() {
+                let key = Kitties::<T>::hashed_key_for(&dna);
+                let old = frame_support::storage::unhashed::get::<OldKitty<T>>(&key)
+                    .ok_or("Undecodable kitty")?;
+                count += 1;
+                if !old.price.is_zero() {
+                    priced += 1;
+                }
+            }
+            Ok((count, priced).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+            let (count, priced): (u64, u64) =
+                Decode::decode(&mut &state[..]).map_err(|_| "Invalid pre-upgrade state")?;
+
+            ensure!(Pallet::<T>::on_chain_storage_version() == 1, "Storage version not updated");
+            let kitties: Vec<Kitty<T>> = Kitties::<T>::iter_values().collect();
+            ensure!(kitties.len() as u64 == count, "Kitties lost in migration");
+            ensure!(
+                kitties.iter().filter(|kitty| kitty.for_sale).count() as u64 == priced,
+                "Sale state doesn't match the old prices"
+            );
+            ensure!(
+                kitties.iter().all(|kitty| kitty.for_sale == kitty.price.is_some()),
+                "Kitty for sale without a price"
+            );
+            Ok(())
+        }
+    }
+}
@@ -14,7 +14,7 @@ type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
 
 parameter_types! {
-    pub const MaxKittyOwned: u32 = 1;
+    pub static MaxKittyOwned: u32 = 1;
 }
 
 frame_support::construct_runtime!(
@@ -84,7 +84,8 @@ pub const SLOT_DURATION: u64 = MILLISECS_PER_BLOCK;
 impl pallet_timestamp::Config for Test {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
-	type OnTimestampSet = Aura;
+	// Tests move the time freely, without matching Aura slots
+	type OnTimestampSet = ();
 	type MinimumPeriod = ConstU64<{ SLOT_DURATION / 2 }>;
 	type WeightInfo = ();
 }
@@ -101,9 +102,29 @@ impl pallet_kitties_myself::Config for Test {
 	type KittyDnaRandom = RandomnessCollectiveFlip;
 	type Currency = Balances;
 	type MaxKittiesOwned = MaxKittyOwned;
+	type MaxDnaAttempts = ConstU32<3>;
+	type KittenAge = ConstU64<{ 10 * MILLISECS_PER_BLOCK }>;
+	type ElderAge = ConstU64<{ 100 * MILLISECS_PER_BLOCK }>;
+	type ExpectedBlockTime = ConstU64<MILLISECS_PER_BLOCK>;
+	type BasePrice = ConstU128<1_000>;
 }
 
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+// Only 100 above the existential deposit
+pub const CHARLIE: u64 = 3;
+// No balance at all
+pub const DAVE: u64 = 4;
+
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 10_000), (BOB, 10_000), (CHARLIE, EXISTENTIAL_DEPOSIT + 100)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	t.into()
 }
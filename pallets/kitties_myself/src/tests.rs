@@ -1,5 +1,6 @@
 use crate::{mock::*, Error, *};
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{assert_noop, assert_ok, traits::Randomness};
+use sp_core::H256;
 
 #[test]
 fn test_mint_work() {
@@ -9,7 +10,7 @@ fn test_mint_work() {
 
 		// check mint kitty success
 		assert_eq!(<KittiesTotal<Test>>::get(), 0);
-		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(1), 100));
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(1), Some(100)));
 		assert_eq!(<KittiesTotal<Test>>::get(), 1);
 
 		// check owned kitty
@@ -19,7 +20,8 @@ fn test_mint_work() {
 		// check kitty exists
 		let kitty_dna = *kitties_owned.last().unwrap();
 		let kitty = <Kitties<Test>>::get(kitty_dna).unwrap();
-		assert_eq!(kitty.price, 100);
+		assert_eq!(kitty.price, Some(100));
+		assert!(kitty.for_sale);
 		assert_eq!(kitty.owner, 1);
 	})
 }
@@ -29,7 +31,7 @@ fn test_transfer_work() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
-		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(1), 100));
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(1), Some(100)));
 		let kitties_owned = <KittiesOwned<Test>>::get(1);
 		assert_eq!(kitties_owned.len(), 1);
         let kitty_dna = *kitties_owned.last().unwrap();
@@ -53,7 +55,7 @@ fn test_transfer_fail() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
-		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(1), 100));
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(1), Some(100)));
 		let kitties_owned = <KittiesOwned<Test>>::get(1);
 		assert_eq!(kitties_owned.len(), 1);
 		let kitty_dna = *kitties_owned.last().unwrap();
@@ -69,3 +71,398 @@ fn test_transfer_fail() {
 		);
 	})
 }
+
+#[test]
+fn test_migrate_to_v1() {
+	use crate::migrations::v1::{MigrateToV1, OldKitty};
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<KittiesMyself>();
+		let put_kitty = |dna: H256, price: u128| {
+			let old = OldKitty::<Test> { dna, owner: 1, price, gender: Gender::Male, created_date: 0 };
+			frame_support::storage::unhashed::put(&<Kitties<Test>>::hashed_key_for(dna), &old);
+		};
+		put_kitty(H256::repeat_byte(1), 0);
+		put_kitty(H256::repeat_byte(2), 100);
+
+		MigrateToV1::<Test>::on_runtime_upgrade();
+
+		// free kitties have no price and are not for sale
+		let kitty = <Kitties<Test>>::get(H256::repeat_byte(1)).unwrap();
+		assert_eq!(kitty.price, None);
+		assert!(!kitty.for_sale);
+		let kitty = <Kitties<Test>>::get(H256::repeat_byte(2)).unwrap();
+		assert_eq!(kitty.price, Some(100));
+		assert!(kitty.for_sale);
+		assert_eq!(kitty.owner, 1);
+		assert_eq!(KittiesMyself::on_chain_storage_version(), 1);
+	})
+}
+
+// Mint a kitty for ALICE at the given price
+fn mint_alice_kitty(price: Option<u128>) -> H256 {
+	System::set_block_number(1);
+	assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(ALICE), price));
+	*<KittiesOwned<Test>>::get(ALICE).last().unwrap()
+}
+
+#[test]
+fn test_set_price_work() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = mint_alice_kitty(None);
+		assert!(!<Kitties<Test>>::get(kitty_dna).unwrap().for_sale);
+
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), kitty_dna, Some(300)));
+		System::assert_last_event(RuntimeEvent::KittiesMyself(Event::PriceSet { kitty_dna, price: Some(300) }));
+		let kitty = <Kitties<Test>>::get(kitty_dna).unwrap();
+		assert_eq!(kitty.price, Some(300));
+		assert!(kitty.for_sale);
+
+		// take it off sale
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), kitty_dna, None));
+		assert!(!<Kitties<Test>>::get(kitty_dna).unwrap().for_sale);
+	})
+}
+
+#[test]
+fn test_set_price_fail() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = mint_alice_kitty(None);
+
+		assert_noop!(
+			KittiesMyself::set_price(RuntimeOrigin::signed(BOB), kitty_dna, Some(300)),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), H256::zero(), Some(300)),
+			Error::<Test>::KittyNotExisted
+		);
+	})
+}
+
+#[test]
+fn test_buy_kitty_work() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = mint_alice_kitty(Some(1_000));
+
+		// the buyer pays the price even when accepting more
+		assert_ok!(KittiesMyself::buy_kitty(RuntimeOrigin::signed(BOB), kitty_dna, 1_500));
+		System::assert_last_event(RuntimeEvent::KittiesMyself(Event::Sold {
+			seller: ALICE,
+			buyer: BOB,
+			kitty_dna,
+			price: 1_000,
+		}));
+		assert_eq!(Balances::free_balance(ALICE), 11_000);
+		assert_eq!(Balances::free_balance(BOB), 9_000);
+
+		// the new owner has to list it again
+		let kitty = <Kitties<Test>>::get(kitty_dna).unwrap();
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(kitty.price, None);
+		assert!(!kitty.for_sale);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).len(), 0);
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).into_inner(), vec![kitty_dna]);
+	})
+}
+
+#[test]
+fn test_buy_kitty_fail() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = mint_alice_kitty(None);
+
+		assert_noop!(
+			KittiesMyself::buy_kitty(RuntimeOrigin::signed(BOB), kitty_dna, 1_000),
+			Error::<Test>::NotForSale
+		);
+
+		// the owner raising the price in front of the purchase
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), kitty_dna, Some(2_000)));
+		assert_noop!(
+			KittiesMyself::buy_kitty(RuntimeOrigin::signed(BOB), kitty_dna, 1_000),
+			Error::<Test>::BidPriceTooLow
+		);
+		assert_noop!(
+			KittiesMyself::buy_kitty(RuntimeOrigin::signed(ALICE), kitty_dna, 2_000),
+			Error::<Test>::NotTransferToSelf
+		);
+	})
+}
+
+#[test]
+fn test_buy_kitty_keeps_buyer_alive() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = mint_alice_kitty(Some(200));
+
+		// CHARLIE would drop below the existential deposit
+		assert_noop!(
+			KittiesMyself::buy_kitty(RuntimeOrigin::signed(CHARLIE), kitty_dna, 200),
+			pallet_balances::Error::<Test>::KeepAlive
+		);
+
+		// but can spend down to it
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), kitty_dna, Some(100)));
+		assert_ok!(KittiesMyself::buy_kitty(RuntimeOrigin::signed(CHARLIE), kitty_dna, 100));
+		assert_eq!(Balances::free_balance(CHARLIE), EXISTENTIAL_DEPOSIT);
+	})
+}
+
+#[test]
+fn test_buy_kitty_below_existential_deposit_of_seller() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = mint_alice_kitty(None);
+		assert_ok!(KittiesMyself::transfer(RuntimeOrigin::signed(ALICE), DAVE, kitty_dna));
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(DAVE), kitty_dna, Some(100)));
+
+		// paying DAVE less than the existential deposit can't create the account
+		assert_noop!(
+			KittiesMyself::buy_kitty(RuntimeOrigin::signed(BOB), kitty_dna, 100),
+			pallet_balances::Error::<Test>::ExistentialDeposit
+		);
+
+		// a price covering the existential deposit goes through
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(DAVE), kitty_dna, Some(EXISTENTIAL_DEPOSIT)));
+		assert_ok!(KittiesMyself::buy_kitty(RuntimeOrigin::signed(BOB), kitty_dna, EXISTENTIAL_DEPOSIT));
+		assert_eq!(Balances::free_balance(DAVE), EXISTENTIAL_DEPOSIT);
+		assert_eq!(<Kitties<Test>>::get(kitty_dna).unwrap().owner, BOB);
+	})
+}
+
+#[test]
+fn test_mint_same_block_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		// the randomness is the same for both mints, which used to loop forever
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(ALICE), None));
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(BOB), None));
+
+		let alice_dna = <KittiesOwned<Test>>::get(ALICE)[0];
+		let bob_dna = <KittiesOwned<Test>>::get(BOB)[0];
+		assert_ne!(alice_dna, bob_dna);
+		assert_eq!(<KittiesTotal<Test>>::get(), 2);
+	})
+}
+
+#[test]
+fn test_mint_retries_taken_dna() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(ALICE), None));
+		let kitty_dna = <KittiesOwned<Test>>::get(ALICE)[0];
+
+		// the kitty moves on, so ALICE mints again from the same seed
+		assert_ok!(KittiesMyself::transfer(RuntimeOrigin::signed(ALICE), BOB, kitty_dna));
+		assert_ok!(KittiesMyself::mint_kitty(RuntimeOrigin::signed(ALICE), None));
+
+		let random = <Test as Config>::KittyDnaRandom::random(&b"dna"[..]).0;
+		let retried = KittiesMyself::dna_of(random, 0, &ALICE, 0, 1);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE)[0], retried);
+	})
+}
+
+#[test]
+fn test_mint_dna_collision_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		// take every DNA ALICE can get within MaxDnaAttempts
+		let random = <Test as Config>::KittyDnaRandom::random(&b"dna"[..]).0;
+		for attempt in 0..3 {
+			let dna = KittiesMyself::dna_of(random, 0, &ALICE, 0, attempt);
+			let kitty = Kitty::<Test> {
+				dna,
+				owner: BOB,
+				price: None,
+				for_sale: false,
+				gender: Gender::Male,
+				created_date: 0,
+			};
+			<Kitties<Test>>::insert(dna, kitty);
+		}
+
+		assert_noop!(
+			KittiesMyself::mint_kitty(RuntimeOrigin::signed(ALICE), None),
+			Error::<Test>::DnaCollision
+		);
+	})
+}
+
+// Put a kitty born at `created_date` in storage
+fn insert_kitty(owner: u64, dna: H256, gender: Gender, created_date: u64) {
+	let kitty = Kitty::<Test> { dna, owner, price: None, for_sale: false, gender, created_date };
+	<Kitties<Test>>::insert(dna, kitty);
+	<KittiesOwned<Test>>::mutate(owner, |owned| owned.try_push(dna).unwrap());
+}
+
+#[test]
+fn test_kitty_age_work() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = H256::repeat_byte(1);
+		insert_kitty(ALICE, kitty_dna, Gender::Male, 0);
+
+		let age = KittiesMyself::kitty_age(kitty_dna).unwrap();
+		assert_eq!((age.blocks, age.millis, age.stage), (0, 0, LifeStage::Kitten));
+
+		Timestamp::set_timestamp(10 * MILLISECS_PER_BLOCK);
+		let age = KittiesMyself::kitty_age(kitty_dna).unwrap();
+		assert_eq!((age.blocks, age.millis, age.stage), (10, 10 * MILLISECS_PER_BLOCK, LifeStage::Adult));
+
+		Timestamp::set_timestamp(100 * MILLISECS_PER_BLOCK);
+		assert_eq!(KittiesMyself::kitty_age(kitty_dna).unwrap().stage, LifeStage::Elder);
+
+		assert_eq!(KittiesMyself::kitty_age(H256::zero()), None);
+	})
+}
+
+#[test]
+fn test_suggested_price_by_stage() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = H256::repeat_byte(1);
+		insert_kitty(ALICE, kitty_dna, Gender::Male, 0);
+		assert_eq!(KittiesMyself::suggested_price(kitty_dna), Some(500));
+
+		Timestamp::set_timestamp(10 * MILLISECS_PER_BLOCK);
+		assert_eq!(KittiesMyself::suggested_price(kitty_dna), Some(1_000));
+
+		Timestamp::set_timestamp(100 * MILLISECS_PER_BLOCK);
+		assert_eq!(KittiesMyself::suggested_price(kitty_dna), Some(250));
+
+		// retired kitties are not for sale
+		assert_ok!(KittiesMyself::retire_kitty(RuntimeOrigin::signed(ALICE), kitty_dna));
+		assert_eq!(KittiesMyself::suggested_price(kitty_dna), None);
+	})
+}
+
+#[test]
+fn test_breed_kitty_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		MaxKittyOwned::set(3);
+		insert_kitty(ALICE, H256::repeat_byte(1), Gender::Male, 0);
+		insert_kitty(ALICE, H256::repeat_byte(2), Gender::Female, 0);
+		Timestamp::set_timestamp(10 * MILLISECS_PER_BLOCK);
+
+		assert_ok!(KittiesMyself::breed_kitty(
+			RuntimeOrigin::signed(ALICE),
+			H256::repeat_byte(1),
+			H256::repeat_byte(2)
+		));
+
+		// the child is born now, as a kitten
+		let child = <KittiesOwned<Test>>::get(ALICE)[2];
+		System::assert_last_event(RuntimeEvent::KittiesMyself(Event::Bred {
+			owner: ALICE,
+			kitty_dna: child,
+			parents: (H256::repeat_byte(1), H256::repeat_byte(2)),
+		}));
+		assert_eq!(KittiesMyself::kitty_age(child).unwrap().stage, LifeStage::Kitten);
+
+		// every bit of the child comes from a parent
+		assert!(child.as_bytes().iter().all(|byte| byte & !0x03 == 0));
+	})
+}
+
+#[test]
+fn test_breed_kitty_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		MaxKittyOwned::set(3);
+		let (dad, mom) = (H256::repeat_byte(1), H256::repeat_byte(2));
+		insert_kitty(ALICE, dad, Gender::Male, 0);
+		insert_kitty(ALICE, mom, Gender::Female, 0);
+
+		// kittens are too young
+		assert_noop!(KittiesMyself::breed_kitty(RuntimeOrigin::signed(ALICE), dad, mom), Error::<Test>::NotAdult);
+
+		Timestamp::set_timestamp(10 * MILLISECS_PER_BLOCK);
+		assert_noop!(KittiesMyself::breed_kitty(RuntimeOrigin::signed(BOB), dad, mom), Error::<Test>::NotOwner);
+		assert_noop!(KittiesMyself::breed_kitty(RuntimeOrigin::signed(ALICE), dad, dad), Error::<Test>::CantBreed);
+
+		// elders are too old
+		Timestamp::set_timestamp(100 * MILLISECS_PER_BLOCK);
+		assert_noop!(KittiesMyself::breed_kitty(RuntimeOrigin::signed(ALICE), dad, mom), Error::<Test>::NotAdult);
+	})
+}
+
+#[test]
+fn test_retire_kitty_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let kitty_dna = H256::repeat_byte(1);
+		insert_kitty(ALICE, kitty_dna, Gender::Male, 0);
+		assert_ok!(KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), kitty_dna, Some(100)));
+
+		Timestamp::set_timestamp(100 * MILLISECS_PER_BLOCK);
+		assert_ok!(KittiesMyself::retire_kitty(RuntimeOrigin::signed(ALICE), kitty_dna));
+		System::assert_last_event(RuntimeEvent::KittiesMyself(Event::Retired { kitty_dna }));
+
+		// taken off sale for good
+		let kitty = <Kitties<Test>>::get(kitty_dna).unwrap();
+		assert!(!kitty.for_sale);
+		assert_eq!(kitty.price, None);
+		assert!(KittiesMyself::kitty_age(kitty_dna).unwrap().retired);
+		assert_noop!(
+			KittiesMyself::set_price(RuntimeOrigin::signed(ALICE), kitty_dna, Some(100)),
+			Error::<Test>::KittyRetired
+		);
+		assert_noop!(
+			KittiesMyself::retire_kitty(RuntimeOrigin::signed(ALICE), kitty_dna),
+			Error::<Test>::KittyRetired
+		);
+	})
+}
+
+#[test]
+fn test_retire_kitty_fail() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = H256::repeat_byte(1);
+		insert_kitty(ALICE, kitty_dna, Gender::Male, 0);
+
+		Timestamp::set_timestamp(10 * MILLISECS_PER_BLOCK);
+		assert_noop!(KittiesMyself::retire_kitty(RuntimeOrigin::signed(ALICE), kitty_dna), Error::<Test>::NotElder);
+
+		Timestamp::set_timestamp(100 * MILLISECS_PER_BLOCK);
+		assert_noop!(KittiesMyself::retire_kitty(RuntimeOrigin::signed(BOB), kitty_dna), Error::<Test>::NotOwner);
+	})
+}
+
+#[test]
+fn test_burn_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let kitty_dna = H256::repeat_byte(1);
+		insert_kitty(ALICE, kitty_dna, Gender::Male, 0);
+		<KittiesTotal<Test>>::put(1);
+		Timestamp::set_timestamp(100 * MILLISECS_PER_BLOCK);
+		assert_ok!(KittiesMyself::retire_kitty(RuntimeOrigin::signed(ALICE), kitty_dna));
+
+		assert_ok!(KittiesMyself::burn(RuntimeOrigin::signed(ALICE), kitty_dna));
+		System::assert_last_event(RuntimeEvent::KittiesMyself(Event::Burned { owner: ALICE, kitty_dna }));
+
+		assert_eq!(<Kitties<Test>>::get(kitty_dna), None);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).len(), 0);
+		assert_eq!(<RetiredKitties<Test>>::get(kitty_dna), None);
+		assert_eq!(<KittiesTotal<Test>>::get(), 0);
+	})
+}
+
+#[test]
+fn test_burn_fail() {
+	new_test_ext().execute_with(|| {
+		let kitty_dna = H256::repeat_byte(1);
+		assert_noop!(KittiesMyself::burn(RuntimeOrigin::signed(ALICE), kitty_dna), Error::<Test>::KittyNotExisted);
+
+		insert_kitty(ALICE, kitty_dna, Gender::Male, 0);
+		<KittiesTotal<Test>>::put(1);
+		assert_noop!(KittiesMyself::burn(RuntimeOrigin::signed(BOB), kitty_dna), Error::<Test>::NotOwner);
+		assert_noop!(
+			KittiesMyself::force_burn(RuntimeOrigin::signed(BOB), kitty_dna),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		assert_ok!(KittiesMyself::force_burn(RuntimeOrigin::root(), kitty_dna));
+		assert_eq!(<KittiesTotal<Test>>::get(), 0);
+	})
+}
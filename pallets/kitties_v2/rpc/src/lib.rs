@@ -0,0 +1,197 @@
+//! RPC interface for the kitties v2 pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_kitties_v2_runtime_api::{
+	AncestorNode, KittiesApi as KittiesRuntimeApi, KittiesLineageApi as KittiesLineageRuntimeApi,
+	KittyDetails,
+};
+
+/// Kitty query RPC methods.
+#[rpc(client, server)]
+pub trait KittiesApi<BlockHash, AccountId, Balance, BlockNumber> {
+	/// The kitties owned by an account.
+	#[method(name = "kitties_byOwner")]
+	fn kitties_by_owner(&self, owner: AccountId, at: Option<BlockHash>) -> RpcResult<Vec<[u8; 16]>>;
+
+	/// The details of a kitty.
+	#[method(name = "kitties_details")]
+	fn kitty(
+		&self,
+		kitty: [u8; 16],
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<KittyDetails<AccountId, Balance, BlockNumber>>>;
+
+	/// A page of at most `limit` kitties for sale with their price, cheapest first. The page
+	/// starts after the `start` price and kitty, the last ones of the previous page.
+	#[method(name = "kitties_forSale")]
+	fn kitties_for_sale(
+		&self,
+		start: Option<(Balance, [u8; 16])>,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<([u8; 16], Balance)>>;
+
+	/// The number of kitties in existence.
+	#[method(name = "kitties_count")]
+	fn kitty_count(&self, at: Option<BlockHash>) -> RpcResult<u64>;
+}
+
+/// Provides the kitty query RPC methods by calling into the runtime.
+pub struct Kitties<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Kitties<C, Block> {
+	/// Create a new instance with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Kitty pedigree RPC methods.
+#[rpc(client, server)]
+pub trait KittiesLineageApi<BlockHash> {
+	/// The ancestry tree of a kitty up to `depth` generations back, breadth first.
+	#[method(name = "kitties_ancestry")]
+	fn ancestry(
+		&self,
+		kitty: [u8; 16],
+		depth: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<AncestorNode>>;
+
+	/// The kitties bred from a kitty.
+	#[method(name = "kitties_children")]
+	fn children(&self, kitty: [u8; 16], at: Option<BlockHash>) -> RpcResult<Vec<[u8; 16]>>;
+
+	/// The ancestors two kitties have in common up to `depth` generations back.
+	#[method(name = "kitties_sharedAncestors")]
+	fn shared_ancestors(
+		&self,
+		kitty_1: [u8; 16],
+		kitty_2: [u8; 16],
+		depth: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<[u8; 16]>>;
+}
+
+/// Provides the kitty pedigree RPC methods by calling into the runtime.
+pub struct KittiesLineage<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> KittiesLineage<C, Block> {
+	/// Create a new instance with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error code for failed runtime API calls.
+const RUNTIME_ERROR: i32 = 1;
+
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(RUNTIME_ERROR, "Runtime error", Some(format!("{:?}", err))))
+		.into()
+}
+
+impl<C, Block, AccountId, Balance, BlockNumber>
+	KittiesApiServer<<Block as BlockT>::Hash, AccountId, Balance, BlockNumber> for Kitties<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: KittiesRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	AccountId: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	fn kitties_by_owner(
+		&self,
+		owner: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<[u8; 16]>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitties_by_owner(&at, owner).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn kitty(
+		&self,
+		kitty: [u8; 16],
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<KittyDetails<AccountId, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty(&at, kitty).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn kitties_for_sale(
+		&self,
+		start: Option<(Balance, [u8; 16])>,
+		limit: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<([u8; 16], Balance)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitties_for_sale(&at, start, limit).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn kitty_count(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<u64> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty_count(&at).map_err(runtime_error_into_rpc_err)
+	}
+}
+
+impl<C, Block> KittiesLineageApiServer<<Block as BlockT>::Hash> for KittiesLineage<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: KittiesLineageRuntimeApi<Block>,
+{
+	fn ancestry(
+		&self,
+		kitty: [u8; 16],
+		depth: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<AncestorNode>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.ancestry(&at, kitty, depth).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn children(
+		&self,
+		kitty: [u8; 16],
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<[u8; 16]>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.children(&at, kitty).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn shared_ancestors(
+		&self,
+		kitty_1: [u8; 16],
+		kitty_2: [u8; 16],
+		depth: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<[u8; 16]>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.shared_ancestors(&at, kitty_1, kitty_2, depth).map_err(runtime_error_into_rpc_err)
+	}
+}
@@ -0,0 +1,45 @@
+//! Runtime API definition for the kitties v2 pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_kitties_v2::{genetics::Traits, AncestorNode, KittyDetails};
+
+sp_api::decl_runtime_apis! {
+	/// Queries on kitties and their owners.
+	pub trait KittiesApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The kitties owned by an account.
+		fn kitties_by_owner(owner: AccountId) -> Vec<[u8; 16]>;
+		/// The details of a kitty, None if there is no such kitty.
+		fn kitty(kitty: [u8; 16]) -> Option<KittyDetails<AccountId, Balance, BlockNumber>>;
+		/// A page of at most `limit` kitties for sale with their price, cheapest first. The page
+		/// starts after the `start` price and kitty, the last ones of the previous page.
+		fn kitties_for_sale(start: Option<(Balance, [u8; 16])>, limit: u32) -> Vec<([u8; 16], Balance)>;
+		/// The number of kitties in existence.
+		fn kitty_count() -> u64;
+	}
+
+	/// Queries on the pedigree of kitties.
+	pub trait KittiesLineageApi {
+		/// The ancestry tree of a kitty up to `depth` generations back, breadth first.
+		fn ancestry(kitty: [u8; 16], depth: u32) -> Vec<AncestorNode>;
+		/// The kitties bred from a kitty.
+		fn children(kitty: [u8; 16]) -> Vec<[u8; 16]>;
+		/// The ancestors two kitties have in common up to `depth` generations back.
+		fn shared_ancestors(kitty_1: [u8; 16], kitty_2: [u8; 16], depth: u32) -> Vec<[u8; 16]>;
+	}
+
+	/// Queries on the genetic traits of kitties.
+	pub trait KittiesGeneticsApi {
+		/// The traits of a kitty, None if there is no such kitty.
+		fn traits(kitty: [u8; 16]) -> Option<Traits>;
+		/// The traits any DNA decodes to.
+		fn decode_dna(dna: [u8; 16]) -> Traits;
+	}
+}
@@ -0,0 +1,87 @@
+// Implementations of the `nonfungibles` traits, so other pallets can hold, move and mint kitties
+// without depending on this pallet. All kitties live in a single collection `()` and are
+// identified by their DNA.
+
+use super::*;
+use frame_support::{
+	dispatch::DispatchResult,
+	ensure,
+	traits::tokens::nonfungibles::{Inspect, InspectEnumerable, Mutate, Transfer},
+};
+use sp_std::{boxed::Box, iter};
+
+impl<T: Config> Inspect<T::AccountId> for Pallet<T> {
+	type ItemId = [u8; 16];
+	type CollectionId = ();
+
+	fn owner(_collection: &Self::CollectionId, item: &Self::ItemId) -> Option<T::AccountId> {
+		Kitties::<T>::get(item).map(|kitty| kitty.owner)
+	}
+
+	// Auctioned and rented kitties are locked until the auction or rental ends
+	fn can_transfer(_collection: &Self::CollectionId, item: &Self::ItemId) -> bool {
+		Kitties::<T>::contains_key(item) &&
+			!Auctions::<T>::contains_key(item) &&
+			!Rentals::<T>::contains_key(item)
+	}
+}
+
+impl<T: Config> InspectEnumerable<T::AccountId> for Pallet<T> {
+	fn collections() -> Box<dyn Iterator<Item = Self::CollectionId>> {
+		Box::new(iter::once(()))
+	}
+
+	fn items(_collection: &Self::CollectionId) -> Box<dyn Iterator<Item = Self::ItemId>> {
+		Box::new(Kitties::<T>::iter_keys())
+	}
+
+	fn owned(who: &T::AccountId) -> Box<dyn Iterator<Item = (Self::CollectionId, Self::ItemId)>> {
+		Box::new(KittiesOwned::<T>::get(who).into_iter().map(|dna| ((), dna)))
+	}
+
+	fn owned_in_collection(
+		_collection: &Self::CollectionId,
+		who: &T::AccountId,
+	) -> Box<dyn Iterator<Item = Self::ItemId>> {
+		Box::new(KittiesOwned::<T>::get(who).into_iter())
+	}
+}
+
+impl<T: Config> Transfer<T::AccountId> for Pallet<T> {
+	// Moves the kitty without payment, like the `transfer` call
+	fn transfer(
+		_collection: &Self::CollectionId,
+		item: &Self::ItemId,
+		destination: &T::AccountId,
+	) -> DispatchResult {
+		Self::do_transfer(*item, destination.clone(), None)
+	}
+}
+
+impl<T: Config> Mutate<T::AccountId> for Pallet<T> {
+	// Mints a generation 0 kitty with the given DNA, its gender is derived from the DNA. The
+	// kitty deposit is reserved from `who`, like for `create_kitty`
+	fn mint_into(
+		_collection: &Self::CollectionId,
+		item: &Self::ItemId,
+		who: &T::AccountId,
+	) -> DispatchResult {
+		Self::mint(who, *item, 0, None)?;
+		Self::reserve_deposit(who, *item)
+	}
+
+	// Burns the kitty like `burn`, only checking the owner when asked to
+	fn burn(
+		_collection: &Self::CollectionId,
+		item: &Self::ItemId,
+		maybe_check_owner: Option<&T::AccountId>,
+	) -> DispatchResult {
+		let kitty = Kitties::<T>::get(item).ok_or(Error::<T>::NoKitty)?;
+		if let Some(owner) = maybe_check_owner {
+			ensure!(kitty.owner == *owner, Error::<T>::NotOwner);
+		}
+		ensure!(!Self::is_auctioned(item), Error::<T>::KittyInAuction);
+		ensure!(!Rentals::<T>::contains_key(item), Error::<T>::KittyRented);
+		Self::do_burn(*item)
+	}
+}
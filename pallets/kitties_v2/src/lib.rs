@@ -1,24 +1,51 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
 pub use pallet::*;
+pub use kitties_primitives::{genetics, Dna, Gender};
+
+pub mod migrations;
+
+mod impl_nonfungibles;
 
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::{
 		pallet_prelude::*,
-		traits::{tokens::ExistenceRequirement, Currency, Randomness}, Twox64Concat, BoundedVec,
+		storage::with_storage_layer,
+		traits::{
+			tokens::ExistenceRequirement, Currency, OnUnbalanced, Randomness,
+			ReservableCurrency, StorageVersion, WithdrawReasons,
+		},
+		Identity, Twox64Concat, BoundedVec,
 	};
 	use frame_system::pallet_prelude::*;
+	use kitties_primitives::{genetics::{self, Traits}, ownership, Gender};
 	use scale_info::TypeInfo;
 	use sp_io::hashing::blake2_128;
-	use sp_runtime::ArithmeticError;
+	use sp_runtime::{traits::{Hash, One, SaturatedConversion, Saturating, Zero}, ArithmeticError, Perbill};
+	use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
 
 	#[cfg(feature = "std")]
 	use frame_support::serde::{Deserialize, Serialize};
 
+	// The current storage version, bumped whenever a migration is needed
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(3);
+
+	// The most kitties listed in a single page of a query
+	const MAX_PAGE_SIZE: u32 = 100;
+
 	// Handles our pallet's currency abstraction
-	type BalanceOf<T> =
+	pub type BalanceOf<T> =
 		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
 
 	// Struct for holding kitty information
 	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
@@ -28,18 +55,130 @@ pub mod pallet {
 		pub price: Option<BalanceOf<T>>, // None assume not for sale
 		pub gender: Gender,
 		pub owner: T::AccountId,
+		pub generation: u32,                       // 0 for minted kitties, highest parent + 1 when bred
+		pub parents: Option<([u8; 16], [u8; 16])>, // None for minted kitties
+		pub next_breed_block: T::BlockNumber,      // can't breed before this block
+	}
+
+	// Struct for holding an English auction of a kitty
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Auction<T: Config> {
+		pub seller: T::AccountId,
+		pub reserve_price: BalanceOf<T>,                       // lowest bid accepted
+		pub end: T::BlockNumber,                               // settled in `on_initialize` of this block
+		pub highest_bid: Option<(T::AccountId, BalanceOf<T>)>, // None while nobody has bid
+	}
+
+	// Struct for holding a Dutch auction of a kitty, its price falls linearly from `start_price` at
+	// block `start` to `floor_price` at block `end` and stays there
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct DutchAuction<T: Config> {
+		pub start_price: BalanceOf<T>,
+		pub floor_price: BalanceOf<T>,
+		pub start: T::BlockNumber,
+		pub end: T::BlockNumber,
+	}
+
+	// Struct for holding a standing offer on a kitty
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Offer<T: Config> {
+		pub buyer: T::AccountId,
+		pub amount: BalanceOf<T>,   // reserved from the buyer until accepted or withdrawn
+		pub expiry: T::BlockNumber, // can't be accepted from this block on
+	}
+
+	// Struct for holding a commitment to mint a kitty, revealed once the randomness of its due
+	// block is drawn
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct MintCommit<T: Config> {
+		pub hash: T::Hash,
+		pub block: T::BlockNumber,
+		pub deposit: BalanceOf<T>, // given back on reveal or once pruned
+	}
+
+	// Struct for holding a proposal to swap the kitty it is keyed by for another kitty
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct SwapProposal<T: Config> {
+		pub proposer: T::AccountId,
+		pub requested: [u8; 16],
+		pub top_up: Option<BalanceOf<T>>, // reserved from the proposer, paid to the counterparty
+	}
+
+	// Struct for holding the account approved to transfer a kitty on behalf of its owner
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Approval<T: Config> {
+		pub delegate: T::AccountId,
+		pub expiry: Option<T::BlockNumber>, // None never expires
+	}
+
+	// Struct for holding the terms an owner offers a kitty for rent at
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct RentalTerms<T: Config> {
+		pub fee: BalanceOf<T>,        // paid up front by the borrower to the owner
+		pub duration: T::BlockNumber, // blocks the borrower holds the breeding rights
+	}
+
+	// Struct for holding the breeding rights of a rented kitty
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Rental<T: Config> {
+		pub borrower: T::AccountId,
+		pub expiry: T::BlockNumber, // rights go back to the owner in `on_initialize` of this block
+	}
+
+	// Struct for holding the royalty paid to the creator of a kitty on every sale
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Royalty<T: Config> {
+		pub creator: T::AccountId,
+		pub rate: Perbill, // `CreatorRoyalty` at mint time
+	}
+
+	// Struct for holding the name and metadata URI of a kitty
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	pub struct KittyMetadata<T: Config> {
+		pub name: BoundedVec<u8, T::MaxNameLength>,
+		pub uri: BoundedVec<u8, T::MaxUriLength>,
+		pub depositor: T::AccountId, // may differ from the owner when kept on transfer
+		pub deposit: BalanceOf<T>,
+	}
+
+	// A kitty in an ancestry tree, listed breadth first from the queried kitty
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct AncestorNode {
+		pub dna: [u8; 16],
+		pub parents: Option<([u8; 16], [u8; 16])>, // None for minted kitties
+		pub depth: u32,                            // generations back from the queried kitty
 	}
 
-	// Set Gender type in kitty struct
-	#[derive(Clone, Encode, Decode, PartialEq, Copy, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	// Everything known about a kitty, as returned to off-chain queries
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
 	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-	pub enum Gender {
-		Female,
-		Male,
+	pub struct KittyDetails<AccountId, Balance, BlockNumber> {
+		pub dna: [u8; 16],
+		pub owner: AccountId,
+		pub price: Option<Balance>,
+		pub gender: Gender,
+		pub generation: u32,
+		pub parents: Option<([u8; 16], [u8; 16])>,
+		pub next_breed_block: BlockNumber,
+		pub name: Option<Vec<u8>>, // None until the owner sets metadata
+		pub uri: Option<Vec<u8>>,
 	}
 
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	//  Configure the pallet by specifying the parameters and types on which it depends
@@ -48,15 +187,118 @@ pub mod pallet {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
-		// The Currency handler for the kitties pallet
-		type Currency: Currency<Self::AccountId>;
+		// The Currency handler for the kitties pallet, bids are held as reserved balance
+		type Currency: ReservableCurrency<Self::AccountId>;
 
 		// The maximum amount of kitties a single account can own
 		#[pallet::constant]
 		type MaxKittiesOwned: Get<u32>;
 
-		// The type of Randomness we want to specify for this pallet
+		// The type of Randomness we want to specify for this pallet, it also seeds revealed mints
 		type KittyRandomness: Randomness<Self::Hash, Self::BlockNumber>;
+
+		// The maximum amount of auctions that can end in the same block
+		#[pallet::constant]
+		type MaxAuctionsPerBlock: Get<u32>;
+
+		// The maximum amount of standing offers on a single kitty
+		#[pallet::constant]
+		type MaxOffersPerKitty: Get<u32>;
+
+		// The blocks a generation 0 kitty rests after breeding, each generation rests one more
+		// such period
+		#[pallet::constant]
+		type BreedCooldown: Get<Self::BlockNumber>;
+
+		// The maximum amount of children recorded for a single kitty, the oldest are forgotten to
+		// make room for new ones
+		#[pallet::constant]
+		type MaxChildren: Get<u32>;
+
+		// The maximum amount of generations walked when looking up ancestors
+		#[pallet::constant]
+		type MaxLineageDepth: Get<u32>;
+
+		// The generations of ancestors two kitties may not share to breed, 0 allows inbreeding
+		#[pallet::constant]
+		type InbreedingCheckDepth: Get<u32>;
+
+		// The maximum amount of rentals that can end in the same block
+		#[pallet::constant]
+		type MaxRentalsPerBlock: Get<u32>;
+
+		// The share of every sale taken as marketplace fee
+		#[pallet::constant]
+		type MarketplaceFee: Get<Perbill>;
+
+		// Where the marketplace fees go
+		type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		// The share of every sale paid to the creator of a kitty, recorded when it is minted
+		#[pallet::constant]
+		type CreatorRoyalty: Get<Perbill>;
+
+		// The chance each locus of a bred kitty mutates into a new byte
+		#[pallet::constant]
+		type MutationRate: Get<Perbill>;
+
+		// The amount reserved from the owner of every minted or bred kitty. Each new owner reserves
+		// it in turn as the previous one gets it back, and it is given back when the kitty is burned
+		#[pallet::constant]
+		type KittyDeposit: Get<BalanceOf<Self>>;
+
+		// The maximum amount of kitties handled by a single batch call
+		#[pallet::constant]
+		type MaxBatchSize: Get<u32>;
+
+		// The amount reserved from the owner setting the name and metadata of a kitty
+		#[pallet::constant]
+		type MetadataDeposit: Get<BalanceOf<Self>>;
+
+		// Where the deposits of names removed by `ForceOrigin` go
+		type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		// The origin allowed to remove offensive names
+		type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		// The minimum length of a kitty name
+		#[pallet::constant]
+		type MinNameLength: Get<u32>;
+
+		// The maximum length of a kitty name
+		#[pallet::constant]
+		type MaxNameLength: Get<u32>;
+
+		// The maximum length of a kitty metadata URI
+		#[pallet::constant]
+		type MaxUriLength: Get<u32>;
+
+		// Whether a transfer clears the name and metadata, giving the deposit back
+		#[pallet::constant]
+		type ClearMetadataOnTransfer: Get<bool>;
+
+		// The maximum amount of swap proposals requesting a single kitty
+		#[pallet::constant]
+		type MaxSwapsPerKitty: Get<u32>;
+
+		// The blocks between committing to a mint and the block whose `KittyRandomness` draws the
+		// DNA, at least 1 as `integrity_test` checks. That randomness must not be known at the commit block yet, so this
+		// should cover how far back the randomness source looks
+		#[pallet::constant]
+		type RevealDelay: Get<Self::BlockNumber>;
+
+		// The blocks after `RevealDelay` a mint commitment may be revealed in before it is pruned
+		#[pallet::constant]
+		type RevealWindow: Get<Self::BlockNumber>;
+
+		// The maximum amount of mint commitments drawing their randomness in the same block
+		#[pallet::constant]
+		type MaxCommitsPerBlock: Get<u32>;
+
+		// The amount reserved from the owner of a mint commitment until it is revealed or pruned,
+		// so filling up `MaxCommitsPerBlock` isn't free
+		#[pallet::constant]
+		type CommitDeposit: Get<BalanceOf<Self>>;
 	}
 
 	#[pallet::event]
@@ -64,12 +306,85 @@ pub mod pallet {
 	pub enum Event<T: Config> {
 		/// A new kitty was successfully created.
 		Created { kitty: [u8; 16], owner: T::AccountId },
+		/// A bred kitty mutated at the given DNA loci.
+		Mutated { kitty: [u8; 16], loci: Vec<u8> },
 		/// The price of a kitty was successfully set.
 		PriceSet { kitty: [u8; 16], price: Option<BalanceOf<T>> },
 		/// A kitty was successfully transferred.
 		Transferred { from: T::AccountId, to: T::AccountId, kitty: [u8; 16] },
-		/// A kitty was successfully sold.
-		Sold { seller: T::AccountId, buyer: T::AccountId, kitty: [u8; 16], price: BalanceOf<T> },
+		/// A kitty was successfully sold. The price is split between the seller, the marketplace
+		/// fee and the royalty paid to the kitty creator, if any.
+		Sold {
+			seller: T::AccountId,
+			buyer: T::AccountId,
+			kitty: [u8; 16],
+			price: BalanceOf<T>,
+			seller_proceeds: BalanceOf<T>,
+			marketplace_fee: BalanceOf<T>,
+			royalty: Option<(T::AccountId, BalanceOf<T>)>,
+		},
+		/// A kitty was put up for auction.
+		AuctionCreated { kitty: [u8; 16], seller: T::AccountId, reserve_price: BalanceOf<T>, end: T::BlockNumber },
+		/// A new highest bid was placed on an auctioned kitty.
+		BidPlaced { kitty: [u8; 16], bidder: T::AccountId, amount: BalanceOf<T> },
+		/// An auction ended and the kitty was sold to the highest bidder.
+		AuctionSettled { kitty: [u8; 16], winner: T::AccountId, price: BalanceOf<T> },
+		/// An auction ended without a sale, the kitty stays with its owner.
+		AuctionExpired { kitty: [u8; 16] },
+		/// A kitty was listed in a Dutch auction.
+		DutchAuctionCreated {
+			kitty: [u8; 16],
+			start_price: BalanceOf<T>,
+			floor_price: BalanceOf<T>,
+			start: T::BlockNumber,
+			end: T::BlockNumber,
+		},
+		/// A Dutch auction was cancelled by the kitty owner.
+		DutchAuctionCancelled { kitty: [u8; 16] },
+		/// A standing offer was made on a kitty.
+		OfferMade { kitty: [u8; 16], buyer: T::AccountId, amount: BalanceOf<T>, expiry: T::BlockNumber },
+		/// A standing offer was accepted by the kitty owner.
+		OfferAccepted { kitty: [u8; 16], seller: T::AccountId, buyer: T::AccountId, price: BalanceOf<T> },
+		/// A standing offer was withdrawn by the buyer.
+		OfferWithdrawn { kitty: [u8; 16], buyer: T::AccountId },
+		/// An account was approved to transfer a kitty.
+		Approved { kitty: [u8; 16], owner: T::AccountId, delegate: T::AccountId, expiry: Option<T::BlockNumber> },
+		/// The approval to transfer a kitty was cancelled.
+		ApprovalCancelled { kitty: [u8; 16], owner: T::AccountId, delegate: T::AccountId },
+		/// An account was made operator of all kitties of an owner.
+		OperatorSet { owner: T::AccountId, operator: T::AccountId, expiry: Option<T::BlockNumber> },
+		/// An operator was removed.
+		OperatorRemoved { owner: T::AccountId, operator: T::AccountId },
+		/// A kitty was offered for rent.
+		RentalOffered { kitty: [u8; 16], fee: BalanceOf<T>, duration: T::BlockNumber },
+		/// A rental offer was cancelled.
+		RentalOfferCancelled { kitty: [u8; 16] },
+		/// A kitty was rented, the borrower holds its breeding rights until `expiry`.
+		Rented { kitty: [u8; 16], owner: T::AccountId, borrower: T::AccountId, fee: BalanceOf<T>, expiry: T::BlockNumber },
+		/// A rental ended and the breeding rights went back to the owner.
+		RentalEnded { kitty: [u8; 16], borrower: T::AccountId },
+		/// A kitty was destroyed.
+		Burned { kitty: [u8; 16], owner: T::AccountId },
+		/// An account committed to minting a kitty.
+		MintCommitted { owner: T::AccountId, hash: T::Hash },
+		/// A swap of the offered kitty for the requested kitty was proposed.
+		SwapProposed { offered: [u8; 16], requested: [u8; 16], proposer: T::AccountId, top_up: Option<BalanceOf<T>> },
+		/// A swap proposal was cancelled by the proposer.
+		SwapCancelled { offered: [u8; 16] },
+		/// Two kitties were swapped.
+		Swapped {
+			offered: [u8; 16],
+			requested: [u8; 16],
+			proposer: T::AccountId,
+			counterparty: T::AccountId,
+			top_up: Option<BalanceOf<T>>,
+		},
+		/// The name and metadata of a kitty were set.
+		MetadataSet { kitty: [u8; 16] },
+		/// The name and metadata of a kitty were cleared.
+		MetadataCleared { kitty: [u8; 16] },
+		/// The name and metadata of a kitty were removed by `ForceOrigin`, slashing the deposit.
+		MetadataKilled { kitty: [u8; 16], deposit: BalanceOf<T> },
 	}
 
 	#[pallet::error]
@@ -90,6 +405,72 @@ pub mod pallet {
 		BidPriceTooLow,
 		/// You need to have two cats with different gender to breed.
 		CantBreed,
+		/// This kitty is being auctioned.
+		KittyInAuction,
+		/// This kitty is not being auctioned.
+		NoAuction,
+		/// The auction end block must be in the future.
+		InvalidAuctionEnd,
+		/// Too many auctions already end in this block.
+		TooManyAuctions,
+		/// The auction is already over.
+		AuctionEnded,
+		/// A Dutch auction must last at least one block and its floor can't exceed its start price.
+		InvalidDutchAuction,
+		/// An offer expiry block must be in the future.
+		InvalidOfferExpiry,
+		/// An account may only have one offer on a kitty.
+		AlreadyOffered,
+		/// A kitty may only have `MaxOffersPerKitty` standing offers.
+		TooManyOffers,
+		/// This offer does not exist!
+		NoOffer,
+		/// This offer has expired.
+		OfferExpired,
+		/// This kitty is resting from its last breeding.
+		BreedingCooldown,
+		/// These kitties are close relatives and can't breed.
+		Inbreeding,
+		/// You are not approved to transfer this kitty.
+		NotApproved,
+		/// An approval expiry block must be in the future.
+		InvalidApprovalExpiry,
+		/// This operator does not exist!
+		NoOperator,
+		/// This kitty is rented out.
+		KittyRented,
+		/// This kitty is not offered for rent.
+		NoRentalOffer,
+		/// A rental must last at least one block.
+		InvalidRentalDuration,
+		/// Too many rentals already end in this block.
+		TooManyRentals,
+		/// A name must be at least `MinNameLength` long.
+		NameTooShort,
+		/// A name must be at most `MaxNameLength` long.
+		NameTooLong,
+		/// A metadata URI must be at most `MaxUriLength` long.
+		UriTooLong,
+		/// This kitty has no name or metadata.
+		NoMetadata,
+		/// This account already has a pending mint commitment.
+		CommitExists,
+		/// This account has no mint commitment!
+		NoCommit,
+		/// The secret doesn't match the mint commitment.
+		InvalidReveal,
+		/// The mint commitment can't be revealed yet.
+		RevealTooEarly,
+		/// The mint commitment wasn't revealed in time.
+		CommitExpired,
+		/// Too many mint commitments draw their randomness in that block.
+		TooManyCommits,
+		/// This kitty is already offered in a swap.
+		SwapExists,
+		/// This swap proposal does not exist!
+		NoSwap,
+		/// A kitty may only be requested by `MaxSwapsPerKitty` swap proposals.
+		TooManySwaps,
 	}
 
 	// Keeps track of the numer of kitties in existence
@@ -104,6 +485,87 @@ pub mod pallet {
 	#[pallet::storage]
 	pub type KittiesOwned<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, BoundedVec<[u8; 16], T::MaxKittiesOwned>, ValueQuery>;
 
+	#[pallet::storage]
+	// Maps the running auction to the kitty DNA
+	pub type Auctions<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], Auction<T>>;
+
+	// Track the auctions ending at each block, so they can be settled in `on_initialize`
+	#[pallet::storage]
+	pub type AuctionsEnding<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, BoundedVec<[u8; 16], T::MaxAuctionsPerBlock>, ValueQuery>;
+
+	// Maps the running Dutch auction to the kitty DNA
+	#[pallet::storage]
+	pub type DutchAuctions<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], DutchAuction<T>>;
+
+	// Track the standing offers on each kitty
+	#[pallet::storage]
+	pub type Offers<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], BoundedVec<Offer<T>, T::MaxOffersPerKitty>, ValueQuery>;
+
+	// Maps the account approved to transfer it to the kitty DNA, cleared on transfer
+	#[pallet::storage]
+	pub type Approvals<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], Approval<T>>;
+
+	// Track the operators allowed to transfer all kitties of an owner, with an optional expiry
+	#[pallet::storage]
+	pub type Operators<T: Config> = StorageDoubleMap<_, Twox64Concat, T::AccountId, Twox64Concat, T::AccountId, Option<T::BlockNumber>>;
+
+	// Maps the rental terms offered by the owner to the kitty DNA
+	#[pallet::storage]
+	pub type RentalOffers<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], RentalTerms<T>>;
+
+	// Maps the breeding rights held by a borrower to the kitty DNA, ownership stays in `Kitties`
+	#[pallet::storage]
+	pub type Rentals<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], Rental<T>>;
+
+	// Track the rentals ending at each block, so they can be returned in `on_initialize`
+	#[pallet::storage]
+	pub type RentalsExpiring<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, BoundedVec<[u8; 16], T::MaxRentalsPerBlock>, ValueQuery>;
+
+	// Maps the creator royalty to the kitty DNA, kitties minted without royalty have no entry
+	#[pallet::storage]
+	pub type Royalties<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], Royalty<T>>;
+
+	// Maps the deposit reserved from the owner to the kitty DNA, genesis and migrated kitties have
+	// no entry
+	#[pallet::storage]
+	pub type KittyDeposits<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], BalanceOf<T>>;
+
+	// Index of the kitties for sale with their price, grouped by the bit length of the price so they
+	// can be listed cheapest first without reading every kitty
+	#[pallet::storage]
+	pub type ForSale<T: Config> = StorageDoubleMap<_, Identity, u8, Twox64Concat, [u8; 16], BalanceOf<T>>;
+
+	// Maps the pending mint commitment to the account that made it
+	#[pallet::storage]
+	pub type MintCommits<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, MintCommit<T>>;
+
+	// Track the accounts whose mint commitments draw their randomness at each block, so it is drawn
+	// in `on_initialize` and the commitments left unrevealed are pruned once the window ends
+	#[pallet::storage]
+	pub type CommitsDue<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, BoundedVec<T::AccountId, T::MaxCommitsPerBlock>, ValueQuery>;
+
+	// Maps the randomness drawn for the mint commitments due at a block to that block, kept until
+	// their reveal window ends
+	#[pallet::storage]
+	pub type MintSeeds<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, T::Hash>;
+
+	// Maps the swap proposal to the offered kitty DNA
+	#[pallet::storage]
+	pub type Swaps<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], SwapProposal<T>>;
+
+	// Track the offered kitties of the swap proposals requesting each kitty, so they can be
+	// cancelled when it moves
+	#[pallet::storage]
+	pub type SwapRequests<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], BoundedVec<[u8; 16], T::MaxSwapsPerKitty>, ValueQuery>;
+
+	// Maps the name and metadata URI to the kitty DNA
+	#[pallet::storage]
+	pub type Metadata<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], KittyMetadata<T>>;
+
+	// Track the children bred from each kitty, parents are kept in the kitty struct
+	#[pallet::storage]
+	pub type KittyChildren<T: Config> = StorageMap<_, Twox64Concat, [u8; 16], BoundedVec<[u8; 16], T::MaxChildren>, ValueQuery>;
+
 	// Our pallet's genesis configuration
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
@@ -122,15 +584,68 @@ pub mod pallet {
 	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
 		fn build(&self){
 			// When building a kitty from genesis config, we require the DNA and Gender to be
-			// supplied
+			// supplied, the Gender being the one encoded in the DNA
 			for (account, dna, gender) in &self.kitties_v2{
-				assert!(Pallet::<T>::mint(account, *dna, *gender).is_ok());
+				assert!(Pallet::<T>::gender_of(dna) == *gender, "Genesis kitty gender doesn't match its DNA");
+				assert!(Pallet::<T>::mint(account, *dna, 0, None).is_ok());
 			}
 		}
 	}
 
 	#[pallet::hooks]
-	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+		// Settle every auction and return every rental ending at this block, draw the randomness of
+		// the mint commitments due and prune those whose reveal window ended
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let ending = AuctionsEnding::<T>::take(n);
+			let settled = ending.len() as u64;
+
+			for kitty_id in ending {
+				Self::settle_auction(kitty_id);
+			}
+
+			let expiring = RentalsExpiring::<T>::take(n);
+			let returned = expiring.len() as u64;
+
+			for kitty_id in expiring {
+				if let Some(rental) = Rentals::<T>::take(&kitty_id) {
+					Self::deposit_event(Event::RentalEnded { kitty: kitty_id, borrower: rental.borrower });
+				}
+			}
+
+			if CommitsDue::<T>::contains_key(n) {
+				MintSeeds::<T>::insert(n, T::KittyRandomness::random(&b"dna&gender"[..]).0);
+			}
+
+			let ended = n.saturating_sub(T::RevealWindow::get().saturating_add(One::one()));
+			let unrevealed = CommitsDue::<T>::take(ended);
+			let pruned = unrevealed.len() as u64;
+
+			for account in unrevealed {
+				if let Some(commit) = MintCommits::<T>::take(&account) {
+					T::Currency::unreserve(&account, commit.deposit);
+				}
+			}
+			MintSeeds::<T>::remove(ended);
+
+			// Each settlement touches the auction, the kitty, both owner lists and both balances,
+			// each returned rental only its usage rights and each pruned commitment itself and the
+			// balance of its owner
+			T::DbWeight::get().reads_writes(4 + 6 * settled + returned + pruned, 5 + 6 * settled + returned + 2 * pruned)
+		}
+
+		// The marketplace fee and the creator royalty both come out of the price, and a mint
+		// commitment is due after the block it is made in
+		fn integrity_test() {
+			assert!(
+				T::MarketplaceFee::get().deconstruct().saturating_add(T::CreatorRoyalty::get().deconstruct()) <=
+					Perbill::one().deconstruct(),
+				"MarketplaceFee and CreatorRoyalty take more than the price"
+			);
+			// Mint randomness is drawn in `on_initialize` of the due block, after the commit block
+			assert!(!T::RevealDelay::get().is_zero(), "RevealDelay must be at least 1");
+		}
+	}
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
@@ -140,43 +655,83 @@ pub mod pallet {
 			// Make sure the caller is from a signed origin
 			let sender = ensure_signed(origin)?;
 
-			// Generate unique DNA and gener using a helper function
-			let (dna, gender) = Self::gen_dna_gender();
+			// Generate unique DNA using a helper function, the gender is derived from it
+			let (dna, _) = Self::gen_dna_gender();
 
-			// Mint new kitty to storage by calling helper function
-			Self::mint(&sender, dna, gender)?;
+			// Mint new kitty to storage by calling helper function, paid by a deposit
+			Self::mint(&sender, dna, 0, None)?;
+			Self::reserve_deposit(&sender, dna)?;
 			Ok(())
 		}
 
-		// Set price for kitty
+		/// Commit to minting a kitty whose DNA is drawn by `reveal_mint` after `RevealDelay`
+		/// blocks. `hash` is the `T::Hashing` hash of the caller and a 32 byte secret, mixed with
+		/// the randomness of the block `RevealDelay` blocks from now, so neither the caller nor
+		/// block authors pick the DNA, whichever block it is revealed in. `CommitDeposit` is
+		/// reserved until the commitment is revealed or pruned.
 		#[pallet::weight(100)]
-		pub fn set_price(origin: OriginFor<T>, kitty_id: [u8; 16], new_price: Option<BalanceOf<T>>) -> DispatchResult{
+		pub fn commit_mint(origin: OriginFor<T>, hash: T::Hash) -> DispatchResult {
 			// Make sure the caller is from a signed origin
 			let sender = ensure_signed(origin)?;
 
-			// Ensure the kitty exists and it called by the kitty owner
-			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
-			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			// Expired commitments are pruned in `on_initialize`, so only a pending one is left
+			ensure!(!MintCommits::<T>::contains_key(&sender), Error::<T>::CommitExists);
 
-			//set price for kitty
-			kitty.price = new_price;
-			Kitties::<T>::insert(&kitty_id, kitty);
+			let now = frame_system::Pallet::<T>::block_number();
+			CommitsDue::<T>::try_mutate(now.saturating_add(T::RevealDelay::get()), |due| {
+				due.try_push(sender.clone())
+			}).map_err(|_| Error::<T>::TooManyCommits)?;
+			let deposit = T::CommitDeposit::get();
+			T::Currency::reserve(&sender, deposit)?;
+			MintCommits::<T>::insert(&sender, MintCommit::<T> { hash, block: now, deposit });
 
 			// emit event
-			Self::deposit_event(Event::PriceSet { kitty: kitty_id, price: new_price });
+			Self::deposit_event(Event::MintCommitted { owner: sender, hash });
+			Ok(())
+		}
+
+		/// Reveal the secret of a mint commitment within `RevealWindow` blocks after the delay,
+		/// minting a kitty like `create_kitty` does.
+		#[pallet::weight(100)]
+		pub fn reveal_mint(origin: OriginFor<T>, secret: [u8; 32]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the secret matches the commitment and it is time to reveal
+			let commit = MintCommits::<T>::take(&sender).ok_or(Error::<T>::NoCommit)?;
+			ensure!(T::Hashing::hash_of(&(&sender, secret)) == commit.hash, Error::<T>::InvalidReveal);
+			let now = frame_system::Pallet::<T>::block_number();
+			let due = commit.block.saturating_add(T::RevealDelay::get());
+			ensure!(now >= due, Error::<T>::RevealTooEarly);
+			ensure!(now <= Self::reveal_deadline(commit.block), Error::<T>::CommitExpired);
+
+			// The randomness is the one drawn at the due block, not at the reveal
+			let seed = MintSeeds::<T>::get(due).ok_or(Error::<T>::RevealTooEarly)?;
+			CommitsDue::<T>::mutate(due, |accounts| accounts.retain(|account| *account != sender));
+			T::Currency::unreserve(&sender, commit.deposit);
+
+			// Mint new kitty to storage by calling helper function, paid by a deposit
+			let dna = Self::reveal_dna(&sender, &secret, seed);
+			Self::mint(&sender, dna, 0, None)?;
+			Self::reserve_deposit(&sender, dna)?;
 			Ok(())
 		}
 
+		// Set price for kitty
+		#[pallet::weight(100)]
+		pub fn set_price(origin: OriginFor<T>, kitty_id: [u8; 16], new_price: Option<BalanceOf<T>>) -> DispatchResult{
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+			Self::do_set_price(&sender, kitty_id, new_price)
+		}
+
 		// Directly transfer a kitty to another recipient.
 		// Any account that holds a kitty can send it to another Account. This will reset the asking price of the kitty, marking it not for sale.
 		#[pallet::weight(100)]
 		pub fn transfer(origin: OriginFor<T>, to: T::AccountId, kitty_id: [u8; 16]) -> DispatchResult {
 			// Make sure the caller is from a signed origin
 			let from = ensure_signed(origin)?;
-			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
-			ensure!(kitty.owner == from, Error::<T>::NotOwner);
-			Self::do_transfer(kitty_id, to, None)?;
-			Ok(())
+			Self::transfer_owned(&from, kitty_id, to)
 		}
 
 		/// Buy a kitty for sale. The `limit_price` parameter is set as a safeguard against the 
@@ -195,104 +750,931 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Transfer several kitties of the caller to the same recipient. Either all kitties are
+		/// transferred or none is.
+		#[pallet::weight(100 * kitty_ids.len().max(1) as u64)]
+		pub fn transfer_batch(origin: OriginFor<T>, to: T::AccountId, kitty_ids: BoundedVec<[u8; 16], T::MaxBatchSize>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let from = ensure_signed(origin)?;
+			for kitty_id in kitty_ids {
+				Self::transfer_owned(&from, kitty_id, to.clone())?;
+			}
+			Ok(())
+		}
+
+		/// Set the price of several kitties of the caller, `None` takes a kitty off sale. Either
+		/// all prices are set or none is.
+		#[pallet::weight(100 * prices.len().max(1) as u64)]
+		pub fn set_price_batch(origin: OriginFor<T>, prices: BoundedVec<([u8; 16], Option<BalanceOf<T>>), T::MaxBatchSize>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+			for (kitty_id, new_price) in prices {
+				Self::do_set_price(&sender, kitty_id, new_price)?;
+			}
+			Ok(())
+		}
+
+		/// Buy several kitties for sale, each with its own `limit_price` like `buy_kitty`. Either
+		/// all kitties are bought or none is.
+		#[pallet::weight(100 * purchases.len().max(1) as u64)]
+		pub fn buy_batch(origin: OriginFor<T>, purchases: BoundedVec<([u8; 16], BalanceOf<T>), T::MaxBatchSize>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let buyer = ensure_signed(origin)?;
+			for (kitty_id, limit_price) in purchases {
+				Self::do_transfer(kitty_id, buyer.clone(), Some(limit_price))?;
+			}
+			Ok(())
+		}
+
 		/// Breed a kitty.
 		///
-		/// Breed two kitties to give birth to a new kitty.
+		/// Breed two kitties to give birth to a new kitty. The newborn is one generation above
+		/// its highest generation parent. Afterwards each parent rests for
+		/// `BreedCooldown * (generation + 1)` blocks before it can breed again.
 		#[pallet::weight(100)]
 		pub fn breed_kitty(origin: OriginFor<T>, parent_1: [u8;16], parent_2: [u8;16]) -> DispatchResult {
 			// Make sure the caller is from a signed origin
 			let sender = ensure_signed(origin)?;
 
 			// Get the kitties
-			let maybe_mom = Kitties::<T>::get(&parent_1).ok_or(Error::<T>::NoKitty)?;
-			let maybe_dad = Kitties::<T>::get(&parent_2).ok_or(Error::<T>::NoKitty)?;
+			let mut maybe_mom = Kitties::<T>::get(&parent_1).ok_or(Error::<T>::NoKitty)?;
+			let mut maybe_dad = Kitties::<T>::get(&parent_2).ok_or(Error::<T>::NoKitty)?;
 
-			// Check both parents are owned by the caller of this function
-			ensure!(maybe_mom.owner == sender, Error::<T>::NotOwner);
-			ensure!(maybe_dad.owner == sender, Error::<T>::NotOwner);
+			// Check the caller of this function holds the breeding rights of both parents, which
+			// belong to the borrower while a kitty is rented
+			ensure!(Self::breeder_of(&parent_1, &maybe_mom) == sender, Error::<T>::NotOwner);
+			ensure!(Self::breeder_of(&parent_2, &maybe_dad) == sender, Error::<T>::NotOwner);
 
 			// Parents must be of opposite genders
 			ensure!(maybe_mom.gender != maybe_dad.gender, Error::<T>::CantBreed);
 
+			// Parents must be done resting
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(maybe_mom.next_breed_block <= now, Error::<T>::BreedingCooldown);
+			ensure!(maybe_dad.next_breed_block <= now, Error::<T>::BreedingCooldown);
+
+			// Parents can't be close relatives unless the runtime allows inbreeding
+			let inbreeding_depth = T::InbreedingCheckDepth::get();
+			if inbreeding_depth > 0 {
+				ensure!(
+					Self::shared_ancestors(parent_1, parent_2, inbreeding_depth).is_empty(),
+					Error::<T>::Inbreeding
+				);
+			}
+
 			// Create new DNA from these parents
-			let (new_dna, new_gender) = Self::breed_dna(&parent_1, &parent_2);
+			let (new_dna, mutated) = Self::breed_dna(&parent_1, &parent_2);
+			let generation = maybe_mom.generation.max(maybe_dad.generation).saturating_add(1);
 
 			// Mint new kitty
-			Self::mint(&sender, new_dna, new_gender)?;
+			Self::mint(&sender, new_dna, generation, Some((parent_1, parent_2)))?;
+			Self::reserve_deposit(&sender, new_dna)?;
+			if !mutated.is_empty() {
+				Self::deposit_event(Event::Mutated { kitty: new_dna, loci: mutated });
+			}
+
+			// Put the parents on cooldown
+			maybe_mom.next_breed_block = now.saturating_add(Self::breed_cooldown(maybe_mom.generation));
+			maybe_dad.next_breed_block = now.saturating_add(Self::breed_cooldown(maybe_dad.generation));
+			Kitties::<T>::insert(&parent_1, maybe_mom);
+			Kitties::<T>::insert(&parent_2, maybe_dad);
 			Ok(())
 
 		}
-	}
 
-	// helper function
-	impl<T: Config> Pallet<T> {
-		// gen and returns DNA & gender
-		fn gen_dna_gender() -> ([u8;16], Gender) {
-			let random = T::KittyRandomness::random(&b"dna&gender"[..]).0;
+		/// Put a kitty up for an English auction.
+		///
+		/// The kitty stays with its owner until `end_block`. The auction is then settled in
+		/// `on_initialize`, selling the kitty to the highest bidder if anyone bid at least
+		/// `reserve_price`. While the auction runs the kitty can't be transferred or priced.
+		#[pallet::weight(100)]
+		pub fn create_auction(origin: OriginFor<T>, kitty_id: [u8; 16], reserve_price: BalanceOf<T>, end_block: T::BlockNumber) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
 
-			// Create randomness payload. Multiple kitties can be generated in the same block,
-			// retaining uniqueness
-			let unique_payload = (
-				random, 
-				frame_system::Pallet::<T>::extrinsic_index().unwrap_or_default(),
-				frame_system::Pallet::<T>::block_number()
-			);
+			// Ensure the kitty exists, it called by the kitty owner and it is not auctioned yet
+			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			ensure!(!Self::is_auctioned(&kitty_id), Error::<T>::KittyInAuction);
+			ensure!(!Rentals::<T>::contains_key(&kitty_id), Error::<T>::KittyRented);
+			ensure!(end_block > frame_system::Pallet::<T>::block_number(), Error::<T>::InvalidAuctionEnd);
 
-			// Turns into a byte array
-			let encoded_payload = unique_payload.encode();
-			let hash = blake2_128(&encoded_payload);
+			// Performs this operation first as it may fail
+			AuctionsEnding::<T>::try_mutate(end_block, |ending| {
+				ending.try_push(kitty_id)
+			}).map_err(|_| Error::<T>::TooManyAuctions)?;
 
-			// Generate Gender
-			if hash[0] % 2 == 0 {
-				// Males are identified by having an even leading byte
-				(hash, Gender::Male)
-			} else {
-				// Females are identified by having an odd leading byte
-				(hash, Gender::Female)
+			// Take the kitty off the fixed price market
+			if kitty.price.is_some() {
+				Self::update_price(&kitty_id, &mut kitty, None);
+				Kitties::<T>::insert(&kitty_id, kitty);
 			}
+
+			Auctions::<T>::insert(&kitty_id, Auction::<T> {
+				seller: sender.clone(),
+				reserve_price,
+				end: end_block,
+				highest_bid: None,
+			});
+
+			// emit event
+			Self::deposit_event(Event::AuctionCreated { kitty: kitty_id, seller: sender, reserve_price, end: end_block });
+			Ok(())
 		}
 
-		// Picks from existing DNA
-		fn mutate_dna_fragment(dna_fragment1: u8, dna_fragment2: u8, random_value: u8) -> u8 {
-			// Given some random u8
-			if random_value % 2 == 0 {
-				// either return `dna_fragment1` if its an even value
-				dna_fragment1
-			} else {
-				// or return `dna_fragment2` if its an odd value
-				dna_fragment2
+		/// Bid on an auctioned kitty.
+		///
+		/// The bid must reach the reserve price and exceed the current highest bid. The bid amount
+		/// is reserved from the bidder and the previous highest bidder gets their funds back.
+		#[pallet::weight(100)]
+		pub fn place_bid(origin: OriginFor<T>, kitty_id: [u8; 16], amount: BalanceOf<T>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let bidder = ensure_signed(origin)?;
+
+			// Ensure the auction is still running and the seller is not bidding
+			let mut auction = Auctions::<T>::get(&kitty_id).ok_or(Error::<T>::NoAuction)?;
+			ensure!(auction.seller != bidder, Error::<T>::TransferToSelf);
+			ensure!(frame_system::Pallet::<T>::block_number() < auction.end, Error::<T>::AuctionEnded);
+
+			// Bids have to reach the reserve price and escalate
+			ensure!(amount >= auction.reserve_price, Error::<T>::BidPriceTooLow);
+			if let Some((_, highest)) = &auction.highest_bid {
+				ensure!(amount > *highest, Error::<T>::BidPriceTooLow);
 			}
-		}
 
-		// Generates a new kitty using existing kitties
-		fn breed_dna(parent1: &[u8;16], parent2: &[u8;16]) -> ([u8;16], Gender) {
-			// Call `gen_dna` to generate random kitty DNA
-			// We don't know what Gender this kitty is yet
-			let (mut new_dna, new_gender) = Self::gen_dna_gender();
+			// Performs this operation first as it may fail
+			T::Currency::reserve(&bidder, amount)?;
 
-			// randomly combine DNA using `mutate_dna_fragment`
-			for i in 0..new_dna.len(){
-				// At this point, `new_dna` is a randomly generated set of bytes, so we can
-				// extract each of its bytes to act as a random value for `mutate_dna_fragment`
-				new_dna[i] = Self::mutate_dna_fragment(parent1[i], parent2[i], new_dna[i])
+			// Give the outbid account its funds back
+			if let Some((outbid, outbid_amount)) = auction.highest_bid.take() {
+				T::Currency::unreserve(&outbid, outbid_amount);
 			}
 
-			// return new DNA and gender
-			(new_dna, new_gender)
+			auction.highest_bid = Some((bidder.clone(), amount));
+			Auctions::<T>::insert(&kitty_id, auction);
+
+			// emit event
+			Self::deposit_event(Event::BidPlaced { kitty: kitty_id, bidder, amount });
+			Ok(())
 		}
 
-		// Mint a kitty
-		fn mint(owner: &T::AccountId, dna: [u8;16], gender: Gender) -> Result<(), DispatchError>{
+		/// List a kitty in a Dutch auction starting now.
+		///
+		/// The price falls linearly from `start_price` to `floor_price` over `duration` blocks,
+		/// then stays at `floor_price` until the kitty is sold or the owner cancels the auction.
+		/// Buyers pay the price of the block they buy in through `buy_kitty`, which still honors
+		/// their `limit_price`.
+		#[pallet::weight(100)]
+		pub fn create_dutch_auction(
+			origin: OriginFor<T>,
+			kitty_id: [u8; 16],
+			start_price: BalanceOf<T>,
+			floor_price: BalanceOf<T>,
+			duration: T::BlockNumber,
+		) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
 
-			// Check if the kitty_dna does not already exist in our storage map
-			ensure!(!Kitties::<T>::contains_key(&dna), Error::<T>::DuplicateKitty);
+			// Ensure the kitty exists, it called by the kitty owner and it is not auctioned yet
+			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			ensure!(!Self::is_auctioned(&kitty_id), Error::<T>::KittyInAuction);
+			ensure!(!Rentals::<T>::contains_key(&kitty_id), Error::<T>::KittyRented);
+			ensure!(!duration.is_zero() && floor_price <= start_price, Error::<T>::InvalidDutchAuction);
 
-			// create a new object
-			let kitty = Kitty::<T> {
+			// Take the kitty off the fixed price market
+			if kitty.price.is_some() {
+				Self::update_price(&kitty_id, &mut kitty, None);
+				Kitties::<T>::insert(&kitty_id, kitty);
+			}
+
+			let start = frame_system::Pallet::<T>::block_number();
+			let end = start.saturating_add(duration);
+			DutchAuctions::<T>::insert(&kitty_id, DutchAuction::<T> { start_price, floor_price, start, end });
+
+			// emit event
+			Self::deposit_event(Event::DutchAuctionCreated { kitty: kitty_id, start_price, floor_price, start, end });
+			Ok(())
+		}
+
+		/// Cancel the Dutch auction of a kitty of the caller.
+		#[pallet::weight(100)]
+		pub fn cancel_dutch_auction(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			DutchAuctions::<T>::take(&kitty_id).ok_or(Error::<T>::NoAuction)?;
+
+			// emit event
+			Self::deposit_event(Event::DutchAuctionCancelled { kitty: kitty_id });
+			Ok(())
+		}
+
+		/// Make a standing offer on any kitty, whether it is for sale or not.
+		///
+		/// The offered amount is reserved from the buyer until the owner accepts the offer, the
+		/// buyer withdraws it, or the kitty is transferred. The offer can be accepted until the
+		/// `expiry` block. Expired offers are released to make room when the kitty has
+		/// `MaxOffersPerKitty` offers.
+		#[pallet::weight(100)]
+		pub fn make_offer(origin: OriginFor<T>, kitty_id: [u8; 16], amount: BalanceOf<T>, expiry: T::BlockNumber) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let buyer = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and the buyer is not the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner != buyer, Error::<T>::TransferToSelf);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(expiry > now, Error::<T>::InvalidOfferExpiry);
+
+			// Release the expired offers to make room for the new one, the buyer's own included
+			let mut offers = Offers::<T>::get(&kitty_id);
+			offers.retain(|offer| {
+				if offer.expiry > now {
+					return true;
+				}
+				T::Currency::unreserve(&offer.buyer, offer.amount);
+				false
+			});
+			ensure!(!offers.iter().any(|offer| offer.buyer == buyer), Error::<T>::AlreadyOffered);
+
+			offers.try_push(Offer::<T> { buyer: buyer.clone(), amount, expiry })
+				.map_err(|_| Error::<T>::TooManyOffers)?;
+
+			// Lock up the offered amount
+			T::Currency::reserve(&buyer, amount)?;
+			Offers::<T>::insert(&kitty_id, offers);
+
+			// emit event
+			Self::deposit_event(Event::OfferMade { kitty: kitty_id, buyer, amount, expiry });
+			Ok(())
+		}
+
+		/// Accept the standing offer of `buyer` on a kitty.
+		///
+		/// The kitty is sold to the buyer for the offered amount through the usual transfer path,
+		/// which releases every other offer on the kitty.
+		#[pallet::weight(100)]
+		pub fn accept_offer(origin: OriginFor<T>, kitty_id: [u8; 16], buyer: T::AccountId) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+
+			// Take the offer out of storage and give its funds back, so they can pay the owner
+			let mut offers = Offers::<T>::get(&kitty_id);
+			let index = offers.iter().position(|offer| offer.buyer == buyer).ok_or(Error::<T>::NoOffer)?;
+			let offer = offers.remove(index);
+			ensure!(offer.expiry > frame_system::Pallet::<T>::block_number(), Error::<T>::OfferExpired);
+			T::Currency::unreserve(&offer.buyer, offer.amount);
+			Offers::<T>::insert(&kitty_id, offers);
+
+			// Sell the kitty at the offered amount
+			Self::update_price(&kitty_id, &mut kitty, Some(offer.amount));
+			Kitties::<T>::insert(&kitty_id, kitty);
+			Self::do_transfer(kitty_id, buyer.clone(), Some(offer.amount))?;
+
+			// emit event
+			Self::deposit_event(Event::OfferAccepted { kitty: kitty_id, seller: sender, buyer, price: offer.amount });
+			Ok(())
+		}
+
+		/// Withdraw a standing offer, giving the reserved funds back to the buyer.
+		#[pallet::weight(100)]
+		pub fn withdraw_offer(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let buyer = ensure_signed(origin)?;
+
+			let mut offers = Offers::<T>::get(&kitty_id);
+			let index = offers.iter().position(|offer| offer.buyer == buyer).ok_or(Error::<T>::NoOffer)?;
+			let offer = offers.remove(index);
+			T::Currency::unreserve(&offer.buyer, offer.amount);
+
+			if offers.is_empty() {
+				Offers::<T>::remove(&kitty_id);
+			} else {
+				Offers::<T>::insert(&kitty_id, offers);
+			}
+
+			// emit event
+			Self::deposit_event(Event::OfferWithdrawn { kitty: kitty_id, buyer });
+			Ok(())
+		}
+
+		/// Propose to swap a kitty of the caller for the `requested` kitty, adding `top_up` to the
+		/// trade. The top-up is reserved from the caller until the owner of the requested kitty
+		/// accepts, the caller cancels, or either kitty moves.
+		#[pallet::weight(100)]
+		pub fn propose_swap(origin: OriginFor<T>, offered: [u8; 16], requested: [u8; 16], top_up: Option<BalanceOf<T>>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let proposer = ensure_signed(origin)?;
+
+			// Ensure both kitties exist and only the offered one belongs to the caller
+			let kitty = Kitties::<T>::get(&offered).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == proposer, Error::<T>::NotOwner);
+			let other = Kitties::<T>::get(&requested).ok_or(Error::<T>::NoKitty)?;
+			ensure!(other.owner != proposer, Error::<T>::TransferToSelf);
+			ensure!(!Swaps::<T>::contains_key(&offered), Error::<T>::SwapExists);
+
+			// Performs this operation first as it may fail
+			SwapRequests::<T>::try_mutate(&requested, |requests| {
+				requests.try_push(offered)
+			}).map_err(|_| Error::<T>::TooManySwaps)?;
+
+			// Lock up the top-up
+			if let Some(amount) = top_up {
+				T::Currency::reserve(&proposer, amount)?;
+			}
+
+			Swaps::<T>::insert(&offered, SwapProposal::<T> { proposer: proposer.clone(), requested, top_up });
+
+			// emit event
+			Self::deposit_event(Event::SwapProposed { offered, requested, proposer, top_up });
+			Ok(())
+		}
+
+		/// Cancel a swap proposal, giving the top-up back.
+		#[pallet::weight(100)]
+		pub fn cancel_swap(origin: OriginFor<T>, offered: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			let swap = Swaps::<T>::get(&offered).ok_or(Error::<T>::NoSwap)?;
+			ensure!(swap.proposer == sender, Error::<T>::NotOwner);
+			Self::cancel_swap_of(&offered);
+
+			// emit event
+			Self::deposit_event(Event::SwapCancelled { offered });
+			Ok(())
+		}
+
+		/// Accept a swap proposal for a kitty of the caller. Both kitties change hands and the
+		/// top-up is paid to the caller, or nothing happens. Like `buy_kitty`, `min_top_up`
+		/// protects the caller from the proposer front-running the transaction with a smaller
+		/// top-up.
+		#[pallet::weight(200)]
+		pub fn accept_swap(origin: OriginFor<T>, offered: [u8; 16], requested: [u8; 16], min_top_up: BalanceOf<T>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let counterparty = ensure_signed(origin)?;
+
+			// Ensure the proposal is for a kitty of the caller
+			let swap = Swaps::<T>::get(&offered).ok_or(Error::<T>::NoSwap)?;
+			ensure!(swap.requested == requested, Error::<T>::NoSwap);
+			let kitty = Kitties::<T>::get(&requested).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == counterparty, Error::<T>::NotOwner);
+			let top_up = swap.top_up.unwrap_or_else(Zero::zero);
+			ensure!(top_up >= min_top_up, Error::<T>::BidPriceTooLow);
+
+			// Release the top-up so it can be paid
+			Self::cancel_swap_of(&offered);
+			if !top_up.is_zero() {
+				T::Currency::transfer(&swap.proposer, &counterparty, top_up, ExistenceRequirement::KeepAlive)?;
+			}
+
+			// Both transfers go through or the dispatch fails as a whole
+			Self::do_transfer(offered, counterparty.clone(), None)?;
+			Self::do_transfer(requested, swap.proposer.clone(), None)?;
+
+			// emit event
+			Self::deposit_event(Event::Swapped { offered, requested, proposer: swap.proposer, counterparty, top_up: swap.top_up });
+			Ok(())
+		}
+
+		/// Approve `delegate` to transfer a kitty on behalf of its owner until the `expiry` block,
+		/// or forever when `None`. A kitty has at most one approved account, which is replaced by
+		/// a new approval and cleared when the kitty is transferred.
+		#[pallet::weight(100)]
+		pub fn approve(origin: OriginFor<T>, kitty_id: [u8; 16], delegate: T::AccountId, expiry: Option<T::BlockNumber>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			Self::ensure_valid_expiry(expiry)?;
+
+			Approvals::<T>::insert(&kitty_id, Approval::<T> { delegate: delegate.clone(), expiry });
+
+			// emit event
+			Self::deposit_event(Event::Approved { kitty: kitty_id, owner: sender, delegate, expiry });
+			Ok(())
+		}
+
+		/// Cancel the approval to transfer a kitty.
+		#[pallet::weight(100)]
+		pub fn cancel_approval(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+
+			let approval = Approvals::<T>::take(&kitty_id).ok_or(Error::<T>::NotApproved)?;
+
+			// emit event
+			Self::deposit_event(Event::ApprovalCancelled { kitty: kitty_id, owner: sender, delegate: approval.delegate });
+			Ok(())
+		}
+
+		/// Allow `operator` to transfer every kitty of the caller until the `expiry` block, or
+		/// forever when `None`.
+		#[pallet::weight(100)]
+		pub fn set_operator(origin: OriginFor<T>, operator: T::AccountId, expiry: Option<T::BlockNumber>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let owner = ensure_signed(origin)?;
+			ensure!(owner != operator, Error::<T>::TransferToSelf);
+			Self::ensure_valid_expiry(expiry)?;
+
+			Operators::<T>::insert(&owner, &operator, expiry);
+
+			// emit event
+			Self::deposit_event(Event::OperatorSet { owner, operator, expiry });
+			Ok(())
+		}
+
+		/// Remove an operator of the caller's kitties.
+		#[pallet::weight(100)]
+		pub fn remove_operator(origin: OriginFor<T>, operator: T::AccountId) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let owner = ensure_signed(origin)?;
+			ensure!(Operators::<T>::contains_key(&owner, &operator), Error::<T>::NoOperator);
+
+			Operators::<T>::remove(&owner, &operator);
+
+			// emit event
+			Self::deposit_event(Event::OperatorRemoved { owner, operator });
+			Ok(())
+		}
+
+		/// Transfer a kitty on behalf of its owner. The caller must be the owner, the account
+		/// approved for this kitty or an operator of the owner, with an approval that has not
+		/// expired.
+		#[pallet::weight(100)]
+		pub fn transfer_from(origin: OriginFor<T>, kitty_id: [u8; 16], to: T::AccountId) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(Self::can_transfer_kitty(&kitty_id, &kitty.owner, &sender), Error::<T>::NotApproved);
+			Self::do_transfer(kitty_id, to, None)?;
+			Ok(())
+		}
+
+		/// Offer a kitty for rent. A borrower pays `fee` to the owner to hold the breeding rights
+		/// of the kitty for `duration` blocks. The kitty stays owned by the owner, but can't be
+		/// transferred or sold while it is rented.
+		#[pallet::weight(100)]
+		pub fn offer_rental(origin: OriginFor<T>, kitty_id: [u8; 16], fee: BalanceOf<T>, duration: T::BlockNumber) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			ensure!(!duration.is_zero(), Error::<T>::InvalidRentalDuration);
+
+			RentalOffers::<T>::insert(&kitty_id, RentalTerms::<T> { fee, duration });
+
+			// emit event
+			Self::deposit_event(Event::RentalOffered { kitty: kitty_id, fee, duration });
+			Ok(())
+		}
+
+		/// Cancel a rental offer. Running rentals are not affected.
+		#[pallet::weight(100)]
+		pub fn cancel_rental_offer(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			ensure!(RentalOffers::<T>::contains_key(&kitty_id), Error::<T>::NoRentalOffer);
+
+			RentalOffers::<T>::remove(&kitty_id);
+
+			// emit event
+			Self::deposit_event(Event::RentalOfferCancelled { kitty: kitty_id });
+			Ok(())
+		}
+
+		/// Rent a kitty offered for rent. Like `buy_kitty`, `max_fee` protects the borrower from
+		/// the owner front-running the transaction with a higher fee.
+		#[pallet::weight(100)]
+		pub fn rent_kitty(origin: OriginFor<T>, kitty_id: [u8; 16], max_fee: BalanceOf<T>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let borrower = ensure_signed(origin)?;
+
+			// Ensure the kitty is offered for rent and free to be rented
+			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			let terms = RentalOffers::<T>::get(&kitty_id).ok_or(Error::<T>::NoRentalOffer)?;
+			ensure!(kitty.owner != borrower, Error::<T>::TransferToSelf);
+			ensure!(!Rentals::<T>::contains_key(&kitty_id), Error::<T>::KittyRented);
+			ensure!(!Self::is_auctioned(&kitty_id), Error::<T>::KittyInAuction);
+			ensure!(max_fee >= terms.fee, Error::<T>::BidPriceTooLow);
+
+			// Performs this operation first as it may fail
+			let expiry = frame_system::Pallet::<T>::block_number().saturating_add(terms.duration);
+			RentalsExpiring::<T>::try_mutate(expiry, |expiring| {
+				expiring.try_push(kitty_id)
+			}).map_err(|_| Error::<T>::TooManyRentals)?;
+
+			// Pay the owner
+			T::Currency::transfer(&borrower, &kitty.owner, terms.fee, ExistenceRequirement::KeepAlive)?;
+
+			// Take the kitty off the fixed price market, as a rented kitty can't be sold
+			if kitty.price.is_some() {
+				Self::update_price(&kitty_id, &mut kitty, None);
+				Kitties::<T>::insert(&kitty_id, &kitty);
+			}
+
+			Rentals::<T>::insert(&kitty_id, Rental::<T> { borrower: borrower.clone(), expiry });
+
+			// emit event
+			Self::deposit_event(Event::Rented { kitty: kitty_id, owner: kitty.owner, borrower, fee: terms.fee, expiry });
+			Ok(())
+		}
+
+		/// Set the name and metadata URI of a kitty of the caller. `MetadataDeposit` is reserved
+		/// from the caller, unless the caller already holds the deposit of the current metadata.
+		#[pallet::weight(100)]
+		pub fn set_metadata(origin: OriginFor<T>, kitty_id: [u8; 16], name: Vec<u8>, uri: Vec<u8>) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+
+			ensure!(name.len() >= T::MinNameLength::get() as usize, Error::<T>::NameTooShort);
+			let name: BoundedVec<_, _> = name.try_into().map_err(|_| Error::<T>::NameTooLong)?;
+			let uri: BoundedVec<_, _> = uri.try_into().map_err(|_| Error::<T>::UriTooLong)?;
+
+			// Keep the deposit of the caller, or swap the deposit of a previous owner for a new one
+			let deposit = match Metadata::<T>::get(&kitty_id) {
+				Some(metadata) if metadata.depositor == sender => metadata.deposit,
+				maybe_metadata => {
+					let deposit = T::MetadataDeposit::get();
+					T::Currency::reserve(&sender, deposit)?;
+					if let Some(metadata) = maybe_metadata {
+						T::Currency::unreserve(&metadata.depositor, metadata.deposit);
+					}
+					deposit
+				},
+			};
+
+			Metadata::<T>::insert(&kitty_id, KittyMetadata::<T> { name, uri, depositor: sender, deposit });
+
+			// emit event
+			Self::deposit_event(Event::MetadataSet { kitty: kitty_id });
+			Ok(())
+		}
+
+		/// Clear the name and metadata URI of a kitty of the caller, giving the deposit back.
+		#[pallet::weight(100)]
+		pub fn clear_metadata(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists and it called by the kitty owner
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			ensure!(Self::clear_metadata_of(&kitty_id), Error::<T>::NoMetadata);
+
+			// emit event
+			Self::deposit_event(Event::MetadataCleared { kitty: kitty_id });
+			Ok(())
+		}
+
+		/// Remove an offensive name and metadata URI. The deposit is slashed.
+		///
+		/// The dispatch origin for this call must match `ForceOrigin`.
+		#[pallet::weight(100)]
+		pub fn kill_metadata(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			let metadata = Metadata::<T>::take(&kitty_id).ok_or(Error::<T>::NoMetadata)?;
+			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&metadata.depositor, metadata.deposit).0);
+
+			// emit event
+			Self::deposit_event(Event::MetadataKilled { kitty: kitty_id, deposit: metadata.deposit });
+			Ok(())
+		}
+
+		/// Destroy a kitty of the caller. Like a transfer, this is not possible while the kitty
+		/// is auctioned or rented.
+		#[pallet::weight(100)]
+		pub fn burn(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			// Make sure the caller is from a signed origin
+			let sender = ensure_signed(origin)?;
+
+			// Ensure the kitty exists, it called by the kitty owner and it is not locked
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == sender, Error::<T>::NotOwner);
+			ensure!(!Self::is_auctioned(&kitty_id), Error::<T>::KittyInAuction);
+			ensure!(!Rentals::<T>::contains_key(&kitty_id), Error::<T>::KittyRented);
+
+			Self::do_burn(kitty_id)
+		}
+
+		/// Destroy any kitty, for moderation. A running auction is cancelled, refunding the
+		/// highest bid, and a running rental ends.
+		#[pallet::weight(100)]
+		pub fn force_burn(origin: OriginFor<T>, kitty_id: [u8; 16]) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::do_burn(kitty_id)
+		}
+	}
+
+	// helper function
+	impl<T: Config> Pallet<T> {
+		/// The ancestry tree of a kitty up to `depth` generations back, capped at
+		/// `MaxLineageDepth`. Nodes are listed breadth first, starting with the kitty itself at
+		/// depth 0. An ancestor reached through several lines is listed once, at the depth it is
+		/// first reached. Returns nothing if the kitty does not exist.
+		pub fn ancestry(kitty_id: [u8; 16], depth: u32) -> Vec<AncestorNode> {
+			let mut nodes = Vec::new();
+			if !Kitties::<T>::contains_key(&kitty_id) {
+				return nodes;
+			}
+
+			let depth = depth.min(T::MaxLineageDepth::get());
+			let mut visited = BTreeSet::from([kitty_id]);
+			let mut current = Vec::from([kitty_id]);
+			for level in 0..=depth {
+				let mut next = Vec::new();
+				for dna in current {
+					let parents = Kitties::<T>::get(&dna).and_then(|kitty| kitty.parents);
+					if let Some((parent_1, parent_2)) = parents {
+						for parent in [parent_1, parent_2] {
+							if visited.insert(parent) {
+								next.push(parent);
+							}
+						}
+					}
+					nodes.push(AncestorNode { dna, parents, depth: level });
+				}
+				current = next;
+			}
+			nodes
+		}
+
+		/// The most recent `MaxChildren` kitties bred from a kitty.
+		pub fn children(kitty_id: [u8; 16]) -> Vec<[u8; 16]> {
+			KittyChildren::<T>::get(&kitty_id).into_inner()
+		}
+
+		/// The ancestors two kitties have in common up to `depth` generations back, capped at
+		/// `MaxLineageDepth`. Each kitty counts as its own ancestor, so a parent and its child
+		/// are related.
+		pub fn shared_ancestors(kitty_1: [u8; 16], kitty_2: [u8; 16], depth: u32) -> Vec<[u8; 16]> {
+			let ancestors_1: BTreeSet<[u8; 16]> =
+				Self::ancestry(kitty_1, depth).into_iter().map(|node| node.dna).collect();
+			let shared: BTreeSet<[u8; 16]> = Self::ancestry(kitty_2, depth)
+				.into_iter()
+				.map(|node| node.dna)
+				.filter(|dna| ancestors_1.contains(dna))
+				.collect();
+			shared.into_iter().collect()
+		}
+
+		/// The kitties owned by an account.
+		pub fn kitties_by_owner(owner: &T::AccountId) -> Vec<[u8; 16]> {
+			KittiesOwned::<T>::get(owner).into_inner()
+		}
+
+		/// The details of a kitty, with its name and metadata URI if any.
+		pub fn kitty_details(kitty_id: [u8; 16]) -> Option<KittyDetails<T::AccountId, BalanceOf<T>, T::BlockNumber>> {
+			let kitty = Kitties::<T>::get(&kitty_id)?;
+			let metadata = Metadata::<T>::get(&kitty_id);
+			Some(KittyDetails {
+				dna: kitty.dna,
+				owner: kitty.owner,
+				price: kitty.price,
+				gender: kitty.gender,
+				generation: kitty.generation,
+				parents: kitty.parents,
+				next_breed_block: kitty.next_breed_block,
+				name: metadata.as_ref().map(|metadata| metadata.name.to_vec()),
+				uri: metadata.map(|metadata| metadata.uri.into_inner()),
+			})
+		}
+
+		/// A page of at most `limit` kitties for sale with their price, capped at 100, cheapest
+		/// first and by DNA at the same price. The page starts after the `start` price and kitty,
+		/// which are those of the last kitty of the previous page, or at the cheapest kitty when
+		/// `None`.
+		pub fn kitties_for_sale(start: Option<(BalanceOf<T>, [u8; 16])>, limit: u32) -> Vec<([u8; 16], BalanceOf<T>)> {
+			let limit = limit.min(MAX_PAGE_SIZE) as usize;
+			let mut page = Vec::new();
+			if limit == 0 {
+				return page;
+			}
+
+			// Only the buckets from the one of the start price on are read
+			let first_bucket = start.map_or(0, |(price, _)| Self::price_bucket(price));
+			for bucket in first_bucket..=u128::BITS as u8 {
+				let mut listed: Vec<(BalanceOf<T>, [u8; 16])> =
+					ForSale::<T>::iter_prefix(bucket).map(|(kitty_id, price)| (price, kitty_id)).collect();
+				listed.sort();
+
+				for (price, kitty_id) in listed {
+					if start.map_or(false, |start| (price, kitty_id) <= start) {
+						continue;
+					}
+					page.push((kitty_id, price));
+					if page.len() == limit {
+						return page;
+					}
+				}
+			}
+			page
+		}
+
+		/// The number of kitties in existence.
+		pub fn kitty_count() -> u64 {
+			CountForKitties::<T>::get()
+		}
+
+		// The traits of a kitty, decoded from its DNA
+		pub fn traits(kitty_id: [u8; 16]) -> Option<Traits> {
+			Kitties::<T>::get(&kitty_id).map(|kitty| genetics::decode(&kitty.dna))
+		}
+
+		// gen and returns DNA & gender
+		fn gen_dna_gender() -> ([u8;16], Gender) {
+			let random = T::KittyRandomness::random(&b"dna&gender"[..]).0;
+
+			// Create randomness payload. Multiple kitties can be generated in the same block,
+			// retaining uniqueness
+			let unique_payload = (
+				random, 
+				frame_system::Pallet::<T>::extrinsic_index().unwrap_or_default(),
+				frame_system::Pallet::<T>::block_number()
+			);
+
+			// Turns into a byte array
+			let encoded_payload = unique_payload.encode();
+			let hash = blake2_128(&encoded_payload);
+
+			(hash, Self::gender_of(&hash))
+		}
+
+		// gen and returns DNA of a revealed mint, from the randomness drawn at its due block
+		fn reveal_dna(owner: &T::AccountId, secret: &[u8; 32], seed: T::Hash) -> [u8;16] {
+			blake2_128(&(seed, secret, owner).encode())
+		}
+
+		// The last block a mint commitment made at `committed` may be revealed in
+		fn reveal_deadline(committed: T::BlockNumber) -> T::BlockNumber {
+			committed.saturating_add(T::RevealDelay::get()).saturating_add(T::RevealWindow::get())
+		}
+
+		// Set the price of a kitty, moving it in the for sale index along with it
+		fn update_price(kitty_id: &[u8; 16], kitty: &mut Kitty<T>, new_price: Option<BalanceOf<T>>) {
+			if let Some(price) = kitty.price {
+				ForSale::<T>::remove(Self::price_bucket(price), kitty_id);
+			}
+			if let Some(price) = new_price {
+				ForSale::<T>::insert(Self::price_bucket(price), kitty_id, price);
+			}
+			kitty.price = new_price;
+		}
+
+		// The for sale index bucket of a price, which is its bit length
+		pub(crate) fn price_bucket(price: BalanceOf<T>) -> u8 {
+			let price: u128 = price.saturated_into();
+			(u128::BITS - price.leading_zeros()) as u8
+		}
+
+		// Set the price of a kitty owned by `sender`
+		fn do_set_price(sender: &T::AccountId, kitty_id: [u8; 16], new_price: Option<BalanceOf<T>>) -> DispatchResult {
+			// Ensure the kitty exists and it called by the kitty owner
+			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == *sender, Error::<T>::NotOwner);
+
+			// An auctioned or rented kitty can't be sold at a fixed price
+			ensure!(!Self::is_auctioned(&kitty_id), Error::<T>::KittyInAuction);
+			ensure!(!Rentals::<T>::contains_key(&kitty_id), Error::<T>::KittyRented);
+
+			//set price for kitty
+			Self::update_price(&kitty_id, &mut kitty, new_price);
+			Kitties::<T>::insert(&kitty_id, kitty);
+
+			// emit event
+			Self::deposit_event(Event::PriceSet { kitty: kitty_id, price: new_price });
+			Ok(())
+		}
+
+		// Transfer a kitty owned by `from` without payment
+		fn transfer_owned(from: &T::AccountId, kitty_id: [u8; 16], to: T::AccountId) -> DispatchResult {
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+			ensure!(kitty.owner == *from, Error::<T>::NotOwner);
+			Self::do_transfer(kitty_id, to, None)
+		}
+
+		/// The current price of a kitty in a Dutch auction.
+		pub fn dutch_auction_price(kitty_id: &[u8; 16]) -> Option<BalanceOf<T>> {
+			let auction = DutchAuctions::<T>::get(kitty_id)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			if now >= auction.end {
+				return Some(auction.floor_price);
+			}
+
+			let elapsed = now.saturating_sub(auction.start);
+			let duration = auction.end.saturating_sub(auction.start);
+			let decay = Perbill::from_rational(elapsed, duration)
+				.mul_floor(auction.start_price.saturating_sub(auction.floor_price));
+			Some(auction.start_price.saturating_sub(decay))
+		}
+
+		// Whether a kitty is in an English or a Dutch auction
+		pub(crate) fn is_auctioned(kitty_id: &[u8; 16]) -> bool {
+			Auctions::<T>::contains_key(kitty_id) || DutchAuctions::<T>::contains_key(kitty_id)
+		}
+
+		// The account holding the breeding rights of a kitty
+		fn breeder_of(kitty_id: &[u8; 16], kitty: &Kitty<T>) -> T::AccountId {
+			Rentals::<T>::get(kitty_id).map_or_else(|| kitty.owner.clone(), |rental| rental.borrower)
+		}
+
+		// Checks an approval expiry is in the future
+		fn ensure_valid_expiry(expiry: Option<T::BlockNumber>) -> DispatchResult {
+			if let Some(expiry) = expiry {
+				ensure!(expiry > frame_system::Pallet::<T>::block_number(), Error::<T>::InvalidApprovalExpiry);
+			}
+			Ok(())
+		}
+
+		// Checks `who` is the owner of a kitty or approved to transfer it
+		fn can_transfer_kitty(kitty_id: &[u8; 16], owner: &T::AccountId, who: &T::AccountId) -> bool {
+			if owner == who {
+				return true;
+			}
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let not_expired = |expiry: Option<T::BlockNumber>| expiry.map_or(true, |expiry| now < expiry);
+
+			let approved = Approvals::<T>::get(kitty_id)
+				.map_or(false, |approval| approval.delegate == *who && not_expired(approval.expiry));
+			let operator = Operators::<T>::get(owner, who).map_or(false, not_expired);
+
+			approved || operator
+		}
+
+		// Derives the gender encoded in a DNA
+		pub(crate) fn gender_of(dna: &[u8; 16]) -> Gender {
+			genetics::gender(dna)
+		}
+
+		// Generates a new kitty using existing kitties, also returns the mutated loci
+		fn breed_dna(parent1: &[u8;16], parent2: &[u8;16]) -> ([u8;16], Vec<u8>) {
+			// Call `gen_dna` to generate random kitty DNA
+			// We don't know what Gender this kitty is yet
+			let (random, _) = Self::gen_dna_gender();
+
+			// At this point, `random` is a randomly generated set of bytes, so we can
+			// use it to pick the alleles the child inherits from each parent
+			let mut new_dna = genetics::inherit(parent1, parent2, &random);
+
+			// Some loci may mutate into alleles neither parent carries
+			let mutated = genetics::mutate(&mut new_dna, T::MutationRate::get(), &random);
+
+			// return new DNA, its gender is derived from it
+			(new_dna, mutated)
+		}
+
+		// The blocks a kitty of the given generation rests after breeding
+		fn breed_cooldown(generation: u32) -> T::BlockNumber {
+			T::BreedCooldown::get().saturating_mul(generation.saturating_add(1).into())
+		}
+
+		// Mint a kitty, its gender is the one encoded in its DNA
+		pub(crate) fn mint(
+			owner: &T::AccountId,
+			dna: [u8;16],
+			generation: u32,
+			parents: Option<([u8; 16], [u8; 16])>,
+		) -> Result<(), DispatchError>{
+
+			// Check if the kitty_dna does not already exist in our storage map
+			ensure!(!Kitties::<T>::contains_key(&dna), Error::<T>::DuplicateKitty);
+
+			// create a new object
+			let kitty = Kitty::<T> {
 				dna,
 				price: None,
-				gender,
-				owner: owner.clone()
+				gender: Self::gender_of(&dna),
+				owner: owner.clone(),
+				generation,
+				parents,
+				next_breed_block: frame_system::Pallet::<T>::block_number(),
 			};
 
 			// Performs this operation first as it may fail
@@ -301,13 +1683,32 @@ pub mod pallet {
 
 			// Append kitty to KittiesOwned
 			KittiesOwned::<T>::try_mutate(&owner, |list_kitty| {
-				list_kitty.try_push(kitty.dna)
+				ownership::insert(list_kitty, kitty.dna)
 			}).map_err(|_| Error::<T>::TooManyOwned)?;
 
+			// Record the kitty as a child of both parents, forgetting the oldest child of a full
+			// index so it never stops a kitty from breeding
+			if let Some((parent_1, parent_2)) = parents {
+				for parent in [parent_1, parent_2] {
+					KittyChildren::<T>::mutate(&parent, |children| {
+						if children.try_push(dna).is_err() && !children.is_empty() {
+							children.remove(0);
+							let _ = children.try_push(dna);
+						}
+					});
+				}
+			}
+
 			// Write new kitty to storage
 			Kitties::<T>::insert(kitty.dna, kitty);
 			CountForKitties::<T>::put(new_count);
 
+			// Record the minter as creator, at the royalty rate in effect now
+			let rate = T::CreatorRoyalty::get();
+			if !rate.is_zero() {
+				Royalties::<T>::insert(&dna, Royalty::<T> { creator: owner.clone(), rate });
+			}
+
 			// Emit event
 			Self::deposit_event(Event::Created{kitty: dna, owner: owner.clone()});
 
@@ -315,54 +1716,245 @@ pub mod pallet {
 
 		}
 
+		// Remove a kitty and everything attached to it from storage
+		pub(crate) fn do_burn(kitty_id: [u8; 16]) -> DispatchResult {
+			let kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
+
+			// Performs this operation first as it may fail
+			let new_count = CountForKitties::<T>::get().checked_sub(1).ok_or(ArithmeticError::Underflow)?;
+
+			// Remove kitty from list of owned kitties
+			let mut owned = KittiesOwned::<T>::get(&kitty.owner);
+			ensure!(ownership::remove(&mut owned, &kitty_id), Error::<T>::NoKitty);
+
+			// Cancel the auctions and the rental, if any
+			if let Some(auction) = Auctions::<T>::take(&kitty_id) {
+				if let Some((bidder, amount)) = auction.highest_bid {
+					T::Currency::unreserve(&bidder, amount);
+				}
+				AuctionsEnding::<T>::mutate(auction.end, |ending| ending.retain(|id| *id != kitty_id));
+			}
+			DutchAuctions::<T>::remove(&kitty_id);
+			if let Some(rental) = Rentals::<T>::take(&kitty_id) {
+				RentalsExpiring::<T>::mutate(rental.expiry, |expiring| expiring.retain(|id| *id != kitty_id));
+			}
+
+			// Take the kitty off sale and give the deposit back to the owner
+			if let Some(price) = kitty.price {
+				ForSale::<T>::remove(Self::price_bucket(price), &kitty_id);
+			}
+			if let Some(deposit) = KittyDeposits::<T>::take(&kitty_id) {
+				T::Currency::unreserve(&kitty.owner, deposit);
+			}
+
+			// Write updates to storage
+			Kitties::<T>::remove(&kitty_id);
+			KittiesOwned::<T>::insert(&kitty.owner, owned);
+			CountForKitties::<T>::put(new_count);
+
+			// The children keep their parents' DNA as lineage, but the burned kitty has no more
+			// children, swaps, offers, approval, rental terms, royalty or metadata
+			Self::clear_swaps(&kitty_id);
+			Self::clear_offers(&kitty_id);
+			Approvals::<T>::remove(&kitty_id);
+			RentalOffers::<T>::remove(&kitty_id);
+			Royalties::<T>::remove(&kitty_id);
+			KittyChildren::<T>::remove(&kitty_id);
+			Self::clear_metadata_of(&kitty_id);
+
+			Self::deposit_event(Event::Burned { kitty: kitty_id, owner: kitty.owner });
+			Ok(())
+		}
+
 		// upgrade storage to transfer kitty
-		fn do_transfer(kitty_id: [u8; 16], to: T::AccountId, maybe_limit_price: Option<BalanceOf<T>>) -> DispatchResult {
+		pub(crate) fn do_transfer(kitty_id: [u8; 16], to: T::AccountId, maybe_limit_price: Option<BalanceOf<T>>) -> DispatchResult {
 			// get the kitty
 			let mut kitty = Kitties::<T>::get(&kitty_id).ok_or(Error::<T>::NoKitty)?;
 			let from = kitty.owner;
 
 			ensure!(from != to, Error::<T>::TransferToSelf);
+			ensure!(!Auctions::<T>::contains_key(&kitty_id), Error::<T>::KittyInAuction);
+			ensure!(!Rentals::<T>::contains_key(&kitty_id), Error::<T>::KittyRented);
 			let mut from_owned = KittiesOwned::<T>::get(&from);
 
 			// Remove kitty from list of owned kitties
-			if let Some(index) = from_owned.iter().position(|&id| id == kitty_id) {
-				from_owned.swap_remove(index);
-			} else { 
-				return Err(Error::<T>::NoKitty.into());
-			}
+			ensure!(ownership::remove(&mut from_owned, &kitty_id), Error::<T>::NoKitty);
 
 			// Add kitty to the list owned kitties
 			let mut to_owned = KittiesOwned::<T>::get(&to);
-			to_owned.try_push(kitty_id).map_err(|_| Error::<T>::TooManyOwned)?;
+			ownership::insert(&mut to_owned, kitty_id).map_err(|_| Error::<T>::TooManyOwned)?;
 
 			// Mutating state here via a balance transfer, so nothing is allowed to fail after this.
 			// The buyer will always be charged the actual price. The limit_price parameter is just a 
 			// protection so the seller isn't able to front-run the transaction.
 			if let Some(limit_price) = maybe_limit_price {
-				// Current kitty price if for sale
-				if let Some(price) = kitty.price {
+				// Current kitty price if for sale, at a fixed price or in a Dutch auction
+				if let Some(price) = kitty.price.or_else(|| Self::dutch_auction_price(&kitty_id)) {
 					ensure!(limit_price >= price, Error::<T>::BidPriceTooLow);
-					// Transfer the amount from buyer to seller
-					T::Currency::transfer(&to, &from, price, ExistenceRequirement::KeepAlive)?;
-					// deposit sold event
-					Self::deposit_event(Event::Sold { seller: from.clone(), buyer: to.clone(), kitty: kitty_id, price });
+					// Split the amount between seller, marketplace and creator
+					Self::pay_sale(kitty_id, &to, &from, price)?;
 				} else {
 					// Kitty price is set to `None` and is not for sale
 					return Err(Error::<T>::NotForSale.into());
 				}
 			}
 
+			// The new owner now pays the deposit for the kitty storage, or the transfer fails
+			Self::move_deposit(kitty_id, &from, &to)?;
+
 			// Transfer succeeded, update the kitty owner and reset the price to `None`.
 			kitty.owner = to.clone();
-			kitty.price = None;
+			Self::update_price(&kitty_id, &mut kitty, None);
 
 			// Write updates to storage
 			Kitties::<T>::insert(&kitty_id, kitty);
 			KittiesOwned::<T>::insert(&to, to_owned);
 			KittiesOwned::<T>::insert(&from, from_owned);
 
+			// Listings, swaps, offers, approvals and rental terms were made by or to the previous owner
+			DutchAuctions::<T>::remove(&kitty_id);
+			Self::clear_swaps(&kitty_id);
+			Self::clear_offers(&kitty_id);
+			Approvals::<T>::remove(&kitty_id);
+			RentalOffers::<T>::remove(&kitty_id);
+			if T::ClearMetadataOnTransfer::get() {
+				Self::clear_metadata_of(&kitty_id);
+			}
+
 			Self::deposit_event(Event::Transferred { from, to, kitty: kitty_id });
 			Ok(())
 		}
+
+		// Charge the buyer of a kitty, paying the marketplace fee and the creator royalty out of
+		// the price and the rest to the seller
+		fn pay_sale(kitty_id: [u8; 16], buyer: &T::AccountId, seller: &T::AccountId, price: BalanceOf<T>) -> DispatchResult {
+			let marketplace_fee = T::MarketplaceFee::get().mul_floor(price);
+
+			// Creators selling their own kitties keep the royalty as part of the proceeds
+			let royalty = Royalties::<T>::get(&kitty_id)
+				.filter(|royalty| royalty.creator != *seller && royalty.creator != *buyer)
+				.map(|royalty| (royalty.creator, royalty.rate.mul_floor(price)))
+				.filter(|(_, amount)| !amount.is_zero());
+
+			let royalty_amount = royalty.as_ref().map_or_else(Zero::zero, |(_, amount)| *amount);
+			let seller_proceeds = price.saturating_sub(marketplace_fee).saturating_sub(royalty_amount);
+
+			T::Currency::transfer(buyer, seller, seller_proceeds, ExistenceRequirement::KeepAlive)?;
+
+			if let Some((creator, amount)) = &royalty {
+				let paid = T::Currency::withdraw(buyer, *amount, WithdrawReasons::TRANSFER, ExistenceRequirement::KeepAlive)?;
+				T::Currency::resolve_creating(creator, paid);
+			}
+
+			if !marketplace_fee.is_zero() {
+				let fee = T::Currency::withdraw(buyer, marketplace_fee, WithdrawReasons::TRANSFER, ExistenceRequirement::KeepAlive)?;
+				T::FeeDestination::on_unbalanced(fee);
+			}
+
+			// deposit sold event
+			Self::deposit_event(Event::Sold {
+				seller: seller.clone(),
+				buyer: buyer.clone(),
+				kitty: kitty_id,
+				price,
+				seller_proceeds,
+				marketplace_fee,
+				royalty,
+			});
+			Ok(())
+		}
+
+		// Reserve the kitty deposit from its first owner
+		pub(crate) fn reserve_deposit(owner: &T::AccountId, kitty_id: [u8; 16]) -> DispatchResult {
+			let deposit = T::KittyDeposit::get();
+			if !deposit.is_zero() {
+				T::Currency::reserve(owner, deposit)?;
+				KittyDeposits::<T>::insert(&kitty_id, deposit);
+			}
+			Ok(())
+		}
+
+		// Give the kitty deposit back to the previous owner and reserve it from the new owner, who
+		// must be able to cover it
+		fn move_deposit(kitty_id: [u8; 16], from: &T::AccountId, to: &T::AccountId) -> DispatchResult {
+			if let Some(deposit) = KittyDeposits::<T>::get(&kitty_id) {
+				T::Currency::unreserve(from, deposit);
+				T::Currency::reserve(to, deposit)?;
+			}
+			Ok(())
+		}
+
+		// Remove the name and metadata of a kitty, giving the deposit back. Returns whether there
+		// was any
+		fn clear_metadata_of(kitty_id: &[u8; 16]) -> bool {
+			match Metadata::<T>::take(kitty_id) {
+				Some(metadata) => {
+					T::Currency::unreserve(&metadata.depositor, metadata.deposit);
+					true
+				},
+				None => false,
+			}
+		}
+
+		// Remove the swap proposal offering a kitty, giving the top-up back
+		fn cancel_swap_of(offered: &[u8; 16]) {
+			if let Some(swap) = Swaps::<T>::take(offered) {
+				if let Some(amount) = swap.top_up {
+					T::Currency::unreserve(&swap.proposer, amount);
+				}
+				let mut requests = SwapRequests::<T>::get(&swap.requested);
+				requests.retain(|id| id != offered);
+				if requests.is_empty() {
+					SwapRequests::<T>::remove(&swap.requested);
+				} else {
+					SwapRequests::<T>::insert(&swap.requested, requests);
+				}
+			}
+		}
+
+		// Cancel the swap proposal offering a kitty and every swap proposal requesting it
+		fn clear_swaps(kitty_id: &[u8; 16]) {
+			Self::cancel_swap_of(kitty_id);
+			for offered in SwapRequests::<T>::get(kitty_id) {
+				Self::cancel_swap_of(&offered);
+			}
+		}
+
+		// Release every standing offer on a kitty
+		fn clear_offers(kitty_id: &[u8; 16]) {
+			for offer in Offers::<T>::take(kitty_id) {
+				T::Currency::unreserve(&offer.buyer, offer.amount);
+			}
+		}
+
+		// Sell an ended auction to its highest bidder through `do_transfer`
+		fn settle_auction(kitty_id: [u8; 16]) {
+			let auction = match Auctions::<T>::take(&kitty_id) {
+				Some(auction) => auction,
+				None => return,
+			};
+
+			if let Some((winner, price)) = auction.highest_bid {
+				// Release the winning bid so it can be paid to the seller
+				T::Currency::unreserve(&winner, price);
+
+				// Either the whole sale goes through or the kitty is left untouched
+				let sold = with_storage_layer(|| -> DispatchResult {
+					Kitties::<T>::try_mutate(&kitty_id, |maybe_kitty| -> DispatchResult {
+						let kitty = maybe_kitty.as_mut().ok_or(Error::<T>::NoKitty)?;
+						Self::update_price(&kitty_id, kitty, Some(price));
+						Ok(())
+					})?;
+					Self::do_transfer(kitty_id, winner.clone(), Some(price))
+				});
+
+				if sold.is_ok() {
+					Self::deposit_event(Event::AuctionSettled { kitty: kitty_id, winner, price });
+					return;
+				}
+			}
+
+			Self::deposit_event(Event::AuctionExpired { kitty: kitty_id });
+		}
 	}
  }
@@ -0,0 +1,398 @@
+// Storage migrations of the kitties pallet. Each version lives in its own module and is wired
+// into the runtime `Executive`.
+
+use super::*;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+};
+use sp_runtime::traits::Zero;
+
+#[cfg(feature = "try-runtime")]
+use frame_support::sp_std::vec::Vec;
+
+pub mod v1 {
+	use super::*;
+
+	// Kitty layout before generation and breeding cooldown tracking
+	#[derive(Encode, Decode)]
+	pub struct OldKitty<T: Config> {
+		pub dna: [u8; 16],
+		pub price: Option<BalanceOf<T>>,
+		pub gender: Gender,
+		pub owner: T::AccountId,
+	}
+
+	// Adds `generation`, `parents` and `next_breed_block` to every kitty. Existing kitties become
+	// generation 0 kitties without parents that can breed right away.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() >= 1 {
+				return T::DbWeight::get().reads(1);
+			}
+
+			let mut translated = 0u64;
+			Kitties::<T>::translate::<OldKitty<T>, _>(|_dna, old| {
+				translated += 1;
+				Some(Kitty::<T> {
+					dna: old.dna,
+					price: old.price,
+					gender: old.gender,
+					owner: old.owner,
+					generation: 0,
+					parents: None,
+					next_breed_block: Zero::zero(),
+				})
+			});
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+			T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			// Keys can be counted without decoding the old values
+			Ok((Kitties::<T>::iter_keys().count() as u64).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let count: u64 = Decode::decode(&mut &state[..]).map_err(|_| "Invalid pre-upgrade state")?;
+
+			ensure!(Pallet::<T>::on_chain_storage_version() >= 1, "Storage version not updated");
+			ensure!(Kitties::<T>::iter().count() as u64 == count, "Kitties lost in migration");
+			Ok(())
+		}
+	}
+}
+
+pub mod v2 {
+	use super::*;
+
+	// Builds the `ForSale` index from the kitties that have a price.
+	pub struct MigrateToV2<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() >= 2 {
+				return T::DbWeight::get().reads(1);
+			}
+
+			let mut reads = 0u64;
+			let mut listed = 0u64;
+			for (kitty_id, kitty) in Kitties::<T>::iter() {
+				reads += 1;
+				if let Some(price) = kitty.price {
+					ForSale::<T>::insert(Pallet::<T>::price_bucket(price), kitty_id, price);
+					listed += 1;
+				}
+			}
+
+			StorageVersion::new(2).put::<Pallet<T>>();
+			T::DbWeight::get().reads_writes(reads + 1, listed + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			// The index is checked afterwards either way, migrated or skipped
+			Ok(Vec::new())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), &'static str> {
+			ensure!(Pallet::<T>::on_chain_storage_version() >= 2, "Storage version not updated");
+			let priced = Kitties::<T>::iter_values().filter(|kitty| kitty.price.is_some()).count();
+			ensure!(ForSale::<T>::iter().count() == priced, "For sale index out of sync");
+			Ok(())
+		}
+	}
+}
+
+pub mod unify {
+	use super::*;
+	use frame_support::{
+		storage::migration::{
+			get_storage_value, have_storage_value, put_storage_value, storage_key_iter, take_storage_item,
+			take_storage_value,
+		},
+		traits::Get,
+		Blake2_128Concat, ReversibleStorageHasher, StorageHasher,
+	};
+	use kitties_primitives::{dna_from_hash, ownership};
+	use sp_io::hashing::blake2_128;
+	use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+
+	#[cfg(feature = "try-runtime")]
+	use sp_std::collections::btree_map::BTreeMap;
+
+	// Kitty layout of `pallet_kitties`, keyed by the hash of the kitty with `Twox64Concat`
+	#[derive(Encode, Decode)]
+	pub struct KittiesKitty<T: Config> {
+		pub dna: [u8; 16],
+		pub price: Option<BalanceOf<T>>,
+		pub gender: Gender,
+		pub owner: T::AccountId,
+	}
+
+	// Kitty layout of `pallet_kitties_myself`, keyed by its DNA with `Blake2_128Concat`. A zero
+	// price means the kitty is free.
+	#[derive(Encode, Decode)]
+	pub struct KittiesMyselfKitty<T: Config, Moment> {
+		pub dna: T::Hash,
+		pub owner: T::AccountId,
+		pub price: BalanceOf<T>,
+		pub gender: Gender,
+		pub created_date: Moment,
+	}
+
+	// Kitty layout of `pallet_kitties_myself` from its storage version 1, a kitty is only for sale
+	// when flagged so
+	#[derive(Encode, Decode)]
+	pub struct KittiesMyselfKittyV1<T: Config, Moment> {
+		pub dna: T::Hash,
+		pub owner: T::AccountId,
+		pub price: Option<BalanceOf<T>>,
+		pub for_sale: bool,
+		pub gender: Gender,
+		pub created_date: Moment,
+	}
+
+	// Moves the kitties of `pallet_kitties` and `pallet_kitties_myself`, deployed under the pallet
+	// names `K` and `M` with `Moment` timestamps, into this pallet. The DNA of a `pallet_kitties`
+	// kitty and the leading 16 bytes of a `pallet_kitties_myself` DNA hash become the kitty ids,
+	// re-derived when already taken, and free or retired kitties are not for sale.
+	//
+	// The move only runs on storage version 2, so later upgrades don't drain kitties minted in the
+	// other pallets since. Every kitty moves unless its owner can't own more kitties here, which
+	// `pre_upgrade` rejects.
+	pub struct MigrateToV3<T, K, M, Moment>(PhantomData<(T, K, M, Moment)>);
+
+	impl<T: Config, K: Get<&'static str>, M: Get<&'static str>, Moment: Decode> OnRuntimeUpgrade
+		for MigrateToV3<T, K, M, Moment>
+	{
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() >= 3 {
+				return T::DbWeight::get().reads(1);
+			}
+
+			let weight = move_from_kitties::<T>(K::get())
+				.saturating_add(move_from_kitties_myself::<T, Moment>(M::get()));
+
+			StorageVersion::new(3).put::<Pallet<T>>();
+			weight.saturating_add(T::DbWeight::get().reads_writes(2, 1))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			// Nothing to check when the move is skipped
+			if Pallet::<T>::on_chain_storage_version() >= 3 {
+				return Ok(Vec::new());
+			}
+
+			// Every owner must be able to own their kitties of both pallets here
+			let mut owners = BTreeMap::<T::AccountId, u32>::new();
+			for owner in legacy_owners::<T, Moment>(K::get(), M::get()) {
+				*owners.entry(owner).or_default() += 1;
+			}
+			for (owner, count) in owners {
+				let owned = KittiesOwned::<T>::decode_len(&owner).unwrap_or_default() as u32;
+				ensure!(owned + count <= T::MaxKittiesOwned::get(), "An owner can't own all their kitties");
+			}
+
+			let legacy = count_kitties::<T, Twox64Concat, KittiesKitty<T>>(K::get()) +
+				count_kitties_myself::<T, Moment>(M::get());
+			Ok((legacy, CountForKitties::<T>::get()).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			ensure!(Pallet::<T>::on_chain_storage_version() >= 3, "Storage version not updated");
+			if state.is_empty() {
+				return Ok(());
+			}
+			let (legacy, count): (u64, u64) =
+				Decode::decode(&mut &state[..]).map_err(|_| "Invalid pre-upgrade state")?;
+
+			let skipped = count_kitties::<T, Twox64Concat, KittiesKitty<T>>(K::get()) +
+				count_kitties_myself::<T, Moment>(M::get());
+			ensure!(skipped == 0, "Kitties left behind in the other pallets");
+			ensure!(CountForKitties::<T>::get() == count + legacy, "Kitties lost in the move");
+			ensure!(Kitties::<T>::iter_keys().count() as u64 == count + legacy, "Kitty count out of sync");
+			Ok(())
+		}
+	}
+
+	// Moves the kitties of `pallet_kitties`, deployed under the pallet name `name`
+	fn move_from_kitties<T: Config>(name: &str) -> Weight {
+		move_kitties::<T, Twox64Concat, KittiesKitty<T>>(name, b"KittyCount", |old| {
+			imported_kitty::<T>(old.dna, old.price, old.gender, old.owner)
+		})
+	}
+
+	// Moves the kitties of `pallet_kitties_myself`, deployed under the pallet name `name`, in the
+	// layout of its storage version
+	fn move_from_kitties_myself<T: Config, Moment: Decode>(name: &str) -> Weight {
+		if !is_kitties_myself_v1(name) {
+			return move_kitties::<T, Blake2_128Concat, KittiesMyselfKitty<T, Moment>>(
+				name,
+				b"KittiesTotal",
+				|old| {
+					let price = if old.price.is_zero() { None } else { Some(old.price) };
+					imported_kitty::<T>(dna_from_hash(old.dna.as_ref()), price, old.gender, old.owner)
+				},
+			)
+		}
+
+		// Retired kitties are never for sale, so they move like any other
+		let weight = move_kitties::<T, Blake2_128Concat, KittiesMyselfKittyV1<T, Moment>>(
+			name,
+			b"KittiesTotal",
+			|old| {
+				let price = if old.for_sale { old.price } else { None };
+				imported_kitty::<T>(dna_from_hash(old.dna.as_ref()), price, old.gender, old.owner)
+			},
+		);
+
+		// Drop the retirement of the moved kitties from the other pallet
+		let pallet = name.as_bytes();
+		let retired: Vec<T::Hash> =
+			storage_key_iter::<T::Hash, Moment, Blake2_128Concat>(pallet, b"RetiredKitties")
+				.map(|(key, _)| key)
+				.collect();
+		let mut dropped = 0u64;
+		for key in &retired {
+			if !have_storage_value(pallet, b"Kitties", &Blake2_128Concat::hash(&key.encode())) {
+				take_storage_item::<_, Moment, Blake2_128Concat>(pallet, b"RetiredKitties", key);
+				dropped += 1;
+			}
+		}
+		weight.saturating_add(T::DbWeight::get().reads_writes(2 * retired.len() as u64, dropped))
+	}
+
+	// Whether `pallet_kitties_myself`, deployed under the pallet name `name`, went through its v1
+	// migration, read from raw storage to stay independent of the pallet
+	fn is_kitties_myself_v1(name: &str) -> bool {
+		get_storage_value::<StorageVersion>(name.as_bytes(), b":__STORAGE_VERSION__:", &[])
+			.map_or(false, |version| version >= 1)
+	}
+
+	// A kitty brought in from another pallet, as a generation 0 kitty without parents
+	fn imported_kitty<T: Config>(
+		dna: [u8; 16],
+		price: Option<BalanceOf<T>>,
+		gender: Gender,
+		owner: T::AccountId,
+	) -> Kitty<T> {
+		Kitty::<T> { dna, price, gender, owner, generation: 0, parents: None, next_breed_block: Zero::zero() }
+	}
+
+	// The DNA a kitty gets here, re-derived from its own until it isn't taken
+	fn free_dna<T: Config>(mut dna: [u8; 16]) -> [u8; 16] {
+		while Kitties::<T>::contains_key(&dna) {
+			dna = blake2_128(&dna);
+		}
+		dna
+	}
+
+	// Moves the `Kitties` of another pallet, whose maps are hashed with `H` and hold `Old` kitties,
+	// along with their `KittiesOwned` entries and counter. A kitty whose owner can't own more
+	// kitties stays behind in the other pallet.
+	fn move_kitties<T: Config, H: ReversibleStorageHasher, Old: Decode>(
+		name: &str,
+		counter: &[u8],
+		into_kitty: impl Fn(Old) -> Kitty<T>,
+	) -> Weight {
+		let pallet = name.as_bytes();
+		let legacy: Vec<(T::Hash, Old)> = storage_key_iter::<T::Hash, Old, H>(pallet, b"Kitties").collect();
+		let mut reads = legacy.len() as u64;
+		let mut writes = 0u64;
+
+		let mut moved = BTreeSet::new();
+		for (key, old) in legacy {
+			let mut kitty = into_kitty(old);
+			kitty.dna = free_dna::<T>(kitty.dna);
+			reads += 2;
+
+			let mut owned = KittiesOwned::<T>::get(&kitty.owner);
+			if ownership::insert(&mut owned, kitty.dna).is_err() {
+				frame_support::log::error!(target: "runtime::kitties", "kitty {:?} not moved, its owner has too many", key);
+				continue;
+			}
+
+			KittiesOwned::<T>::insert(&kitty.owner, owned);
+			if let Some(price) = kitty.price {
+				ForSale::<T>::insert(Pallet::<T>::price_bucket(price), kitty.dna, price);
+				writes += 1;
+			}
+			Kitties::<T>::insert(kitty.dna, kitty);
+			take_storage_item::<_, Old, H>(pallet, b"Kitties", key);
+			moved.insert(key);
+			writes += 3;
+		}
+
+		// Drop the moved kitties from the owner index of the other pallet
+		let owners: Vec<(T::AccountId, Vec<T::Hash>)> =
+			storage_key_iter::<T::AccountId, Vec<T::Hash>, H>(pallet, b"KittiesOwned").collect();
+		reads += owners.len() as u64;
+		for (owner, mut owned) in owners {
+			owned.retain(|key| !moved.contains(key));
+			if owned.is_empty() {
+				take_storage_item::<_, Vec<T::Hash>, H>(pallet, b"KittiesOwned", &owner);
+			} else {
+				put_storage_value(pallet, b"KittiesOwned", &H::hash(&owner.encode()), owned);
+			}
+			writes += 1;
+		}
+
+		// Both counters keep counting the kitties each pallet holds
+		let count = moved.len() as u64;
+		let remaining = take_storage_value::<u64>(pallet, counter, &[]).unwrap_or_default().saturating_sub(count);
+		if !remaining.is_zero() {
+			put_storage_value(pallet, counter, &[], remaining);
+		}
+		CountForKitties::<T>::mutate(|total| *total = total.saturating_add(count));
+
+		frame_support::log::info!(target: "runtime::kitties", "moved {} kitties from {}", count, name);
+		T::DbWeight::get().reads_writes(reads + 2, writes + 2)
+	}
+
+	// The owners of the kitties in both other pallets, once per kitty
+	#[cfg(feature = "try-runtime")]
+	fn legacy_owners<T: Config, Moment: Decode>(kitties: &str, kitties_myself: &str) -> Vec<T::AccountId> {
+		let mut owners: Vec<T::AccountId> =
+			storage_key_iter::<T::Hash, KittiesKitty<T>, Twox64Concat>(kitties.as_bytes(), b"Kitties")
+				.map(|(_, kitty)| kitty.owner)
+				.collect();
+		let pallet = kitties_myself.as_bytes();
+		if is_kitties_myself_v1(kitties_myself) {
+			owners.extend(
+				storage_key_iter::<T::Hash, KittiesMyselfKittyV1<T, Moment>, Blake2_128Concat>(pallet, b"Kitties")
+					.map(|(_, kitty)| kitty.owner),
+			);
+		} else {
+			owners.extend(
+				storage_key_iter::<T::Hash, KittiesMyselfKitty<T, Moment>, Blake2_128Concat>(pallet, b"Kitties")
+					.map(|(_, kitty)| kitty.owner),
+			);
+		}
+		owners
+	}
+
+	// The kitties in another pallet
+	#[cfg(feature = "try-runtime")]
+	fn count_kitties<T: Config, H: ReversibleStorageHasher, Old: Decode>(name: &str) -> u64 {
+		storage_key_iter::<T::Hash, Old, H>(name.as_bytes(), b"Kitties").count() as u64
+	}
+
+	// The kitties in `pallet_kitties_myself`, in the layout of its storage version
+	#[cfg(feature = "try-runtime")]
+	fn count_kitties_myself<T: Config, Moment: Decode>(name: &str) -> u64 {
+		if is_kitties_myself_v1(name) {
+			count_kitties::<T, Blake2_128Concat, KittiesMyselfKittyV1<T, Moment>>(name)
+		} else {
+			count_kitties::<T, Blake2_128Concat, KittiesMyselfKitty<T, Moment>>(name)
+		}
+	}
+}
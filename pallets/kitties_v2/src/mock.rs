@@ -0,0 +1,179 @@
+use crate as pallet_kitties_v2;
+use crate::Gender;
+use frame_support::traits::{
+	ConstU16, ConstU32, ConstU64, ConstU128, Currency, GenesisBuild, OnInitialize, OnUnbalanced,
+	Randomness,
+};
+use frame_support::parameter_types;
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, Hash, IdentityLookup},
+	Perbill,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+parameter_types! {
+	pub const MaxKittiesOwned: u32 = 10;
+	pub const MaxAuctionsPerBlock: u32 = 2;
+	pub const MaxOffersPerKitty: u32 = 2;
+	pub const MaxSwapsPerKitty: u32 = 2;
+	pub const MarketplaceFee: Perbill = Perbill::from_percent(10);
+	pub const CreatorRoyalty: Perbill = Perbill::from_percent(5);
+	pub const KittyDeposit: Balance = 100;
+	pub const MaxBatchSize: u32 = 3;
+	pub const MetadataDeposit: Balance = 50;
+	pub const CommitDeposit: Balance = 10;
+	pub static ClearMetadataOnTransfer: bool = true;
+	// Tests set these to make breeding deterministic
+	pub static MutationRate: Perbill = Perbill::from_percent(0);
+	pub static RandomSeed: H256 = H256::zero();
+}
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		KittiesV2: pallet_kitties_v2,
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+/// Existential deposit.
+pub const EXISTENTIAL_DEPOSIT: u128 = 500;
+pub type Balance = u128;
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ConstU32<50>;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	/// The type for recording an account's balance.
+	type Balance = Balance;
+	/// The ubiquitous event type.
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
+	type AccountStore = System;
+	type WeightInfo = pallet_balances::weights::SubstrateWeight<Test>;
+}
+
+impl pallet_kitties_v2::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type MaxKittiesOwned = MaxKittiesOwned;
+	type KittyRandomness = MockRandomness;
+	type MaxAuctionsPerBlock = MaxAuctionsPerBlock;
+	type MaxOffersPerKitty = MaxOffersPerKitty;
+	type BreedCooldown = ConstU64<5>;
+	type MaxChildren = ConstU32<10>;
+	type MaxLineageDepth = ConstU32<4>;
+	type InbreedingCheckDepth = ConstU32<2>;
+	type MaxRentalsPerBlock = ConstU32<2>;
+	type MarketplaceFee = MarketplaceFee;
+	type FeeDestination = MarketplaceFees;
+	type CreatorRoyalty = CreatorRoyalty;
+	type MutationRate = MutationRate;
+	type KittyDeposit = KittyDeposit;
+	type MaxBatchSize = MaxBatchSize;
+	type MetadataDeposit = MetadataDeposit;
+	type Slashed = ();
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type MinNameLength = ConstU32<3>;
+	type MaxNameLength = ConstU32<8>;
+	type MaxUriLength = ConstU32<16>;
+	type ClearMetadataOnTransfer = ClearMetadataOnTransfer;
+	type MaxSwapsPerKitty = MaxSwapsPerKitty;
+	type RevealDelay = ConstU64<3>;
+	type RevealWindow = ConstU64<5>;
+	type MaxCommitsPerBlock = ConstU32<2>;
+	type CommitDeposit = CommitDeposit;
+}
+
+// Randomness derived from RandomSeed only
+pub struct MockRandomness;
+impl Randomness<H256, u64> for MockRandomness {
+	fn random(subject: &[u8]) -> (H256, u64) {
+		(BlakeTwo256::hash_of(&(RandomSeed::get(), subject)), System::block_number())
+	}
+}
+
+// Pays the marketplace fees to FEE_ACCOUNT
+pub struct MarketplaceFees;
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Test>> for MarketplaceFees {
+	fn on_nonzero_unbalanced(amount: pallet_balances::NegativeImbalance<Test>) {
+		Balances::resolve_creating(&FEE_ACCOUNT, amount);
+	}
+}
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+pub const DAVE: u64 = 4;
+pub const FEE_ACCOUNT: u64 = 99;
+
+// Kitties owned by ALICE at genesis
+pub const KITTY_1: [u8; 16] = [1; 16];
+pub const KITTY_2: [u8; 16] = [2; 16];
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 10_000), (BOB, 10_000), (CHARLIE, 10_000), (DAVE, 10_000), (FEE_ACCOUNT, 10_000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	pallet_kitties_v2::GenesisConfig::<Test> {
+		kitties_v2: vec![(ALICE, KITTY_1, Gender::Female), (ALICE, KITTY_2, Gender::Male)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = t.into();
+	// Events are not deposited at block 0
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+// Move forward to block `n`, running the pallet's `on_initialize` hook on the way
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		KittiesV2::on_initialize(System::block_number());
+	}
+}
@@ -0,0 +1,2146 @@
+use crate::{mock::*, Error, *};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::H256;
+use sp_runtime::{traits::{BlakeTwo256, Hash}, Perbill};
+
+#[test]
+fn test_create_auction_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(500)));
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+
+		// check auction is stored and queued for settlement
+		let auction = <Auctions<Test>>::get(KITTY_1).unwrap();
+		assert_eq!(auction.seller, ALICE);
+		assert_eq!(auction.reserve_price, 100);
+		assert_eq!(auction.highest_bid, None);
+		assert_eq!(<AuctionsEnding<Test>>::get(10).into_inner(), vec![KITTY_1]);
+
+		// check kitty is taken off the fixed price market
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().price, None);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::AuctionCreated {
+			kitty: KITTY_1,
+			seller: ALICE,
+			reserve_price: 100,
+			end: 10,
+		}));
+	})
+}
+
+#[test]
+fn test_create_auction_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::create_auction(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 1),
+			Error::<Test>::InvalidAuctionEnd
+		);
+
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_noop!(
+			KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10),
+			Error::<Test>::KittyInAuction
+		);
+
+		// MaxAuctionsPerBlock is 2
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_2, 100, 10));
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let kitty = <KittiesOwned<Test>>::get(BOB)[0];
+		assert_noop!(
+			KittiesV2::create_auction(RuntimeOrigin::signed(BOB), kitty, 100, 10),
+			Error::<Test>::TooManyAuctions
+		);
+	})
+}
+
+#[test]
+fn test_auctioned_kitty_is_locked() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+
+		assert_noop!(
+			KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_1),
+			Error::<Test>::KittyInAuction
+		);
+		assert_noop!(
+			KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(100)),
+			Error::<Test>::KittyInAuction
+		);
+	})
+}
+
+#[test]
+fn test_place_bid_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+
+		assert_ok!(KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+		assert_eq!(Balances::reserved_balance(BOB), 100);
+
+		// check outbid funds are given back
+		assert_ok!(KittiesV2::place_bid(RuntimeOrigin::signed(CHARLIE), KITTY_1, 150));
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(Balances::reserved_balance(CHARLIE), 150);
+		assert_eq!(<Auctions<Test>>::get(KITTY_1).unwrap().highest_bid, Some((CHARLIE, 150)));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::BidPlaced {
+			kitty: KITTY_1,
+			bidder: CHARLIE,
+			amount: 150,
+		}));
+	})
+}
+
+#[test]
+fn test_place_bid_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 100),
+			Error::<Test>::NoAuction
+		);
+
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_noop!(
+			KittiesV2::place_bid(RuntimeOrigin::signed(ALICE), KITTY_1, 100),
+			Error::<Test>::TransferToSelf
+		);
+		assert_noop!(
+			KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 99),
+			Error::<Test>::BidPriceTooLow
+		);
+
+		// bids must escalate
+		assert_ok!(KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+		assert_noop!(
+			KittiesV2::place_bid(RuntimeOrigin::signed(CHARLIE), KITTY_1, 100),
+			Error::<Test>::BidPriceTooLow
+		);
+
+		// bidder must be able to reserve the amount
+		assert!(KittiesV2::place_bid(RuntimeOrigin::signed(CHARLIE), KITTY_1, 20_000).is_err());
+	})
+}
+
+#[test]
+fn test_auction_settles_to_highest_bidder() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+		assert_ok!(KittiesV2::place_bid(RuntimeOrigin::signed(CHARLIE), KITTY_1, 150));
+
+		run_to_block(9);
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, ALICE);
+
+		run_to_block(10);
+		System::assert_has_event(RuntimeEvent::KittiesV2(Event::AuctionSettled {
+			kitty: KITTY_1,
+			winner: CHARLIE,
+			price: 150,
+		}));
+
+		// check kitty and funds moved
+		let kitty = <Kitties<Test>>::get(KITTY_1).unwrap();
+		assert_eq!(kitty.owner, CHARLIE);
+		assert_eq!(kitty.price, None);
+		assert!(<KittiesOwned<Test>>::get(CHARLIE).contains(&KITTY_1));
+		assert!(!<KittiesOwned<Test>>::get(ALICE).contains(&KITTY_1));
+		// ALICE created the kitty, so she keeps the royalty and pays the marketplace fee
+		assert_eq!(Balances::free_balance(ALICE), 10_135);
+		assert_eq!(Balances::free_balance(FEE_ACCOUNT), 10_015);
+		assert_eq!(Balances::free_balance(CHARLIE), 9_850);
+		assert_eq!(Balances::reserved_balance(CHARLIE), 0);
+		assert_eq!(Balances::free_balance(BOB), 10_000);
+
+		// check auction is cleaned up
+		assert_eq!(<Auctions<Test>>::get(KITTY_1), None);
+		assert_eq!(<AuctionsEnding<Test>>::get(10).len(), 0);
+		assert_noop!(
+			KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 200),
+			Error::<Test>::NoAuction
+		);
+	})
+}
+
+#[test]
+fn test_auction_without_bids_expires() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+
+		run_to_block(10);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::AuctionExpired { kitty: KITTY_1 }));
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, ALICE);
+		assert_eq!(<Auctions<Test>>::get(KITTY_1), None);
+
+		// kitty can be traded again
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_1));
+	})
+}
+
+#[test]
+fn test_make_offer_work() {
+	new_test_ext().execute_with(|| {
+		// kitty is not for sale
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().price, None);
+
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(CHARLIE), KITTY_1, 200, 10));
+		assert_eq!(Balances::reserved_balance(BOB), 100);
+		assert_eq!(Balances::reserved_balance(CHARLIE), 200);
+		assert_eq!(<Offers<Test>>::get(KITTY_1).len(), 2);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::OfferMade {
+			kitty: KITTY_1,
+			buyer: CHARLIE,
+			amount: 200,
+			expiry: 10,
+		}));
+	})
+}
+
+#[test]
+fn test_make_offer_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::make_offer(RuntimeOrigin::signed(BOB), [9; 16], 100, 10),
+			Error::<Test>::NoKitty
+		);
+		assert_noop!(
+			KittiesV2::make_offer(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10),
+			Error::<Test>::TransferToSelf
+		);
+		assert_noop!(
+			KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 1),
+			Error::<Test>::InvalidOfferExpiry
+		);
+		assert!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 20_000, 10).is_err());
+
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10));
+		assert_noop!(
+			KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 200, 10),
+			Error::<Test>::AlreadyOffered
+		);
+
+		// MaxOffersPerKitty is 2
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(CHARLIE), KITTY_1, 100, 10));
+		assert_noop!(
+			KittiesV2::make_offer(RuntimeOrigin::signed(DAVE), KITTY_1, 100, 10),
+			Error::<Test>::TooManyOffers
+		);
+	})
+}
+
+#[test]
+fn test_make_offer_replaces_expired_offers() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 5));
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(CHARLIE), KITTY_1, 100, 10));
+
+		run_to_block(5);
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(DAVE), KITTY_1, 100, 10));
+
+		// check expired offer is released
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		let buyers: Vec<u64> = <Offers<Test>>::get(KITTY_1).iter().map(|offer| offer.buyer).collect();
+		assert_eq!(buyers, vec![CHARLIE, DAVE]);
+	})
+}
+
+#[test]
+fn test_make_offer_again_after_expiry() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 5));
+
+		// check the expired offer doesn't block a new one from the same buyer
+		run_to_block(5);
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 200, 10));
+		assert_eq!(Balances::reserved_balance(BOB), 200);
+		let offers = <Offers<Test>>::get(KITTY_1);
+		assert_eq!(offers.len(), 1);
+		assert_eq!(offers[0].amount, 200);
+		assert_eq!(offers[0].expiry, 10);
+	})
+}
+
+#[test]
+fn test_accept_offer_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(CHARLIE), KITTY_1, 200, 10));
+
+		assert_ok!(KittiesV2::accept_offer(RuntimeOrigin::signed(ALICE), KITTY_1, BOB));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::OfferAccepted {
+			kitty: KITTY_1,
+			seller: ALICE,
+			buyer: BOB,
+			price: 100,
+		}));
+
+		// check kitty and funds moved
+		let kitty = <Kitties<Test>>::get(KITTY_1).unwrap();
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(kitty.price, None);
+		assert_eq!(Balances::free_balance(ALICE), 10_090);
+		assert_eq!(Balances::free_balance(BOB), 9_900);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+
+		// check other offers are released
+		assert_eq!(Balances::reserved_balance(CHARLIE), 0);
+		assert_eq!(<Offers<Test>>::get(KITTY_1).len(), 0);
+	})
+}
+
+#[test]
+fn test_accept_offer_fail() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 5));
+
+		assert_noop!(
+			KittiesV2::accept_offer(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::accept_offer(RuntimeOrigin::signed(ALICE), KITTY_1, CHARLIE),
+			Error::<Test>::NoOffer
+		);
+
+		run_to_block(5);
+		assert_noop!(
+			KittiesV2::accept_offer(RuntimeOrigin::signed(ALICE), KITTY_1, BOB),
+			Error::<Test>::OfferExpired
+		);
+	})
+}
+
+#[test]
+fn test_withdraw_offer_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::withdraw_offer(RuntimeOrigin::signed(BOB), KITTY_1),
+			Error::<Test>::NoOffer
+		);
+
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::withdraw_offer(RuntimeOrigin::signed(BOB), KITTY_1));
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(!<Offers<Test>>::contains_key(KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::OfferWithdrawn {
+			kitty: KITTY_1,
+			buyer: BOB,
+		}));
+	})
+}
+
+#[test]
+fn test_transfer_clears_offers() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), CHARLIE, KITTY_1));
+
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(!<Offers<Test>>::contains_key(KITTY_1));
+	})
+}
+
+#[test]
+fn test_breed_kitty_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+
+		// check newborn records its lineage
+		let kitties_owned = <KittiesOwned<Test>>::get(ALICE);
+		assert_eq!(kitties_owned.len(), 3);
+		let child = <Kitties<Test>>::get(kitties_owned[2]).unwrap();
+		assert_eq!(child.generation, 1);
+		assert_eq!(child.parents, Some((KITTY_1, KITTY_2)));
+
+		// check parents rest BreedCooldown blocks
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().next_breed_block, 6);
+		assert_eq!(<Kitties<Test>>::get(KITTY_2).unwrap().next_breed_block, 6);
+	})
+}
+
+#[test]
+fn test_breed_kitty_cooldown() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+		assert_noop!(
+			KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2),
+			Error::<Test>::BreedingCooldown
+		);
+
+		run_to_block(5);
+		assert_noop!(
+			KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2),
+			Error::<Test>::BreedingCooldown
+		);
+
+		run_to_block(6);
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+	})
+}
+
+#[test]
+fn test_breed_cooldown_grows_with_generation() {
+	new_test_ext().execute_with(|| {
+		<Kitties<Test>>::mutate(KITTY_1, |kitty| kitty.as_mut().unwrap().generation = 2);
+
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().next_breed_block, 16);
+		assert_eq!(<Kitties<Test>>::get(KITTY_2).unwrap().next_breed_block, 6);
+
+		let child = <KittiesOwned<Test>>::get(ALICE)[2];
+		assert_eq!(<Kitties<Test>>::get(child).unwrap().generation, 3);
+	})
+}
+
+#[test]
+fn test_migrate_to_v1() {
+	use crate::migrations::v1::{MigrateToV1, OldKitty};
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<KittiesV2>();
+		let old = OldKitty::<Test> { dna: KITTY_1, price: Some(100), gender: Gender::Male, owner: BOB };
+		frame_support::storage::unhashed::put(&<Kitties<Test>>::hashed_key_for(KITTY_1), &old);
+		let old = OldKitty::<Test> { dna: KITTY_2, price: None, gender: Gender::Female, owner: ALICE };
+		frame_support::storage::unhashed::put(&<Kitties<Test>>::hashed_key_for(KITTY_2), &old);
+
+		MigrateToV1::<Test>::on_runtime_upgrade();
+
+		let kitty = <Kitties<Test>>::get(KITTY_1).unwrap();
+		assert_eq!(kitty.price, Some(100));
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(kitty.generation, 0);
+		assert_eq!(kitty.parents, None);
+		assert_eq!(kitty.next_breed_block, 0);
+		assert_eq!(<Kitties<Test>>::get(KITTY_2).unwrap().owner, ALICE);
+		assert_eq!(StorageVersion::get::<KittiesV2>(), 1);
+	})
+}
+
+// Insert a kitty owned by ALICE with the given parents straight into storage
+fn insert_kitty(dna: [u8; 16], parents: Option<([u8; 16], [u8; 16])>) {
+	<Kitties<Test>>::insert(
+		dna,
+		Kitty::<Test> {
+			dna,
+			price: None,
+			gender: KittiesV2::gender_of(&dna),
+			owner: ALICE,
+			generation: if parents.is_some() { 1 } else { 0 },
+			parents,
+			next_breed_block: 0,
+		},
+	);
+	<KittiesOwned<Test>>::mutate(ALICE, |owned| owned.try_push(dna).unwrap());
+}
+
+#[test]
+fn test_breed_kitty_records_children() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+
+		let child = <KittiesOwned<Test>>::get(ALICE)[2];
+		assert_eq!(KittiesV2::children(KITTY_1), vec![child]);
+		assert_eq!(KittiesV2::children(KITTY_2), vec![child]);
+		assert_eq!(KittiesV2::children(child), Vec::<[u8; 16]>::new());
+	})
+}
+
+#[test]
+fn test_children_index_keeps_the_latest() {
+	new_test_ext().execute_with(|| {
+		// check breeding goes on past `MaxChildren`, forgetting the oldest children
+		let accounts = [ALICE, BOB, CHARLIE, DAVE];
+		for i in 0..12u8 {
+			let owner = accounts[i as usize % accounts.len()];
+			assert_ok!(KittiesV2::mint(&owner, [10 + i; 16], 1, Some((KITTY_1, KITTY_2))));
+		}
+		let children = KittiesV2::children(KITTY_1);
+		assert_eq!(children.len(), 10);
+		assert_eq!(children[0], [12; 16]);
+		assert_eq!(children[9], [21; 16]);
+	})
+}
+
+#[test]
+fn test_ancestry() {
+	new_test_ext().execute_with(|| {
+		let child = [3; 16];
+		let grandchild = [4; 16];
+		let other = [5; 16];
+		insert_kitty(child, Some((KITTY_1, KITTY_2)));
+		insert_kitty(other, None);
+		insert_kitty(grandchild, Some((child, other)));
+
+		let ancestry = KittiesV2::ancestry(grandchild, 2);
+		assert_eq!(
+			ancestry,
+			vec![
+				AncestorNode { dna: grandchild, parents: Some((child, other)), depth: 0 },
+				AncestorNode { dna: child, parents: Some((KITTY_1, KITTY_2)), depth: 1 },
+				AncestorNode { dna: other, parents: None, depth: 1 },
+				AncestorNode { dna: KITTY_1, parents: None, depth: 2 },
+				AncestorNode { dna: KITTY_2, parents: None, depth: 2 },
+			]
+		);
+
+		// check depth is honored
+		assert_eq!(KittiesV2::ancestry(grandchild, 1).len(), 3);
+		assert_eq!(KittiesV2::ancestry(grandchild, 0).len(), 1);
+		assert!(KittiesV2::ancestry([9; 16], 2).is_empty());
+	})
+}
+
+#[test]
+fn test_ancestry_lists_shared_ancestors_once() {
+	new_test_ext().execute_with(|| {
+		let brother = [3; 16];
+		let sister = [4; 16];
+		let inbred = [5; 16];
+		insert_kitty(brother, Some((KITTY_1, KITTY_2)));
+		insert_kitty(sister, Some((KITTY_1, KITTY_2)));
+		insert_kitty(inbred, Some((brother, sister)));
+
+		let dnas: Vec<[u8; 16]> = KittiesV2::ancestry(inbred, 2).into_iter().map(|node| node.dna).collect();
+		assert_eq!(dnas, vec![inbred, brother, sister, KITTY_1, KITTY_2]);
+	})
+}
+
+#[test]
+fn test_shared_ancestors() {
+	new_test_ext().execute_with(|| {
+		let brother = [3; 16];
+		let sister = [4; 16];
+		let stranger = [5; 16];
+		insert_kitty(brother, Some((KITTY_1, KITTY_2)));
+		insert_kitty(sister, Some((KITTY_1, KITTY_2)));
+		insert_kitty(stranger, None);
+
+		assert_eq!(KittiesV2::shared_ancestors(brother, sister, 1), vec![KITTY_1, KITTY_2]);
+		assert_eq!(KittiesV2::shared_ancestors(brother, KITTY_2, 1), vec![KITTY_2]);
+		assert!(KittiesV2::shared_ancestors(brother, stranger, 1).is_empty());
+		assert!(KittiesV2::shared_ancestors(brother, sister, 0).is_empty());
+	})
+}
+
+#[test]
+fn test_breed_kitty_inbreeding_fail() {
+	new_test_ext().execute_with(|| {
+		let brother = [3; 16];
+		let sister = [4; 16];
+		let stranger = [6; 16];
+		insert_kitty(brother, Some((KITTY_1, KITTY_2)));
+		insert_kitty(sister, Some((KITTY_1, KITTY_2)));
+		insert_kitty(stranger, None);
+
+		assert_noop!(
+			KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), brother, sister),
+			Error::<Test>::Inbreeding
+		);
+		assert_noop!(
+			KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), brother, KITTY_2),
+			Error::<Test>::Inbreeding
+		);
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), brother, stranger));
+	})
+}
+
+#[test]
+fn test_nonfungibles_inspect() {
+	use frame_support::traits::tokens::nonfungibles::{Inspect, InspectEnumerable};
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(<KittiesV2 as Inspect<u64>>::owner(&(), &KITTY_1), Some(ALICE));
+		assert_eq!(<KittiesV2 as Inspect<u64>>::owner(&(), &[9; 16]), None);
+
+		// check auctioned kitties can't be transferred
+		assert!(<KittiesV2 as Inspect<u64>>::can_transfer(&(), &KITTY_1));
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert!(!<KittiesV2 as Inspect<u64>>::can_transfer(&(), &KITTY_1));
+
+		assert_eq!(<KittiesV2 as InspectEnumerable<u64>>::collections().collect::<Vec<_>>(), vec![()]);
+		let mut items = <KittiesV2 as InspectEnumerable<u64>>::items(&()).collect::<Vec<_>>();
+		items.sort();
+		assert_eq!(items, vec![KITTY_1, KITTY_2]);
+		assert_eq!(
+			<KittiesV2 as InspectEnumerable<u64>>::owned(&ALICE).collect::<Vec<_>>(),
+			vec![((), KITTY_1), ((), KITTY_2)]
+		);
+		assert_eq!(
+			<KittiesV2 as InspectEnumerable<u64>>::owned_in_collection(&(), &BOB).count(),
+			0
+		);
+	})
+}
+
+#[test]
+fn test_nonfungibles_transfer_and_mint() {
+	use frame_support::traits::tokens::nonfungibles::{Mutate, Transfer};
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(<KittiesV2 as Transfer<u64>>::transfer(&(), &KITTY_1, &BOB));
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, BOB);
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).into_inner(), vec![KITTY_1]);
+
+		let dna = [8; 16];
+		assert_ok!(<KittiesV2 as Mutate<u64>>::mint_into(&(), &dna, &CHARLIE));
+		let kitty = <Kitties<Test>>::get(dna).unwrap();
+		assert_eq!(kitty.owner, CHARLIE);
+		assert_eq!(kitty.gender, Gender::Male);
+		assert_noop!(
+			<KittiesV2 as Mutate<u64>>::mint_into(&(), &dna, &CHARLIE),
+			Error::<Test>::DuplicateKitty
+		);
+	})
+}
+
+#[test]
+fn test_approve_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::approve(RuntimeOrigin::signed(BOB), KITTY_1, BOB, None),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::approve(RuntimeOrigin::signed(ALICE), KITTY_1, BOB, Some(1)),
+			Error::<Test>::InvalidApprovalExpiry
+		);
+
+		assert_ok!(KittiesV2::approve(RuntimeOrigin::signed(ALICE), KITTY_1, BOB, None));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::Approved {
+			kitty: KITTY_1,
+			owner: ALICE,
+			delegate: BOB,
+			expiry: None,
+		}));
+
+		// check approved account can transfer, others can't
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(CHARLIE), KITTY_1, CHARLIE),
+			Error::<Test>::NotApproved
+		);
+		assert_ok!(KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, CHARLIE));
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, CHARLIE);
+
+		// check approval is cleared on transfer
+		assert_eq!(<Approvals<Test>>::get(KITTY_1), None);
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotApproved
+		);
+	})
+}
+
+#[test]
+fn test_approval_expires() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::approve(RuntimeOrigin::signed(ALICE), KITTY_1, BOB, Some(5)));
+
+		run_to_block(5);
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotApproved
+		);
+	})
+}
+
+#[test]
+fn test_cancel_approval_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::cancel_approval(RuntimeOrigin::signed(ALICE), KITTY_1),
+			Error::<Test>::NotApproved
+		);
+
+		assert_ok!(KittiesV2::approve(RuntimeOrigin::signed(ALICE), KITTY_1, BOB, None));
+		assert_ok!(KittiesV2::cancel_approval(RuntimeOrigin::signed(ALICE), KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::ApprovalCancelled {
+			kitty: KITTY_1,
+			owner: ALICE,
+			delegate: BOB,
+		}));
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotApproved
+		);
+	})
+}
+
+#[test]
+fn test_operator_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::set_operator(RuntimeOrigin::signed(ALICE), ALICE, None),
+			Error::<Test>::TransferToSelf
+		);
+
+		assert_ok!(KittiesV2::set_operator(RuntimeOrigin::signed(ALICE), BOB, Some(5)));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::OperatorSet {
+			owner: ALICE,
+			operator: BOB,
+			expiry: Some(5),
+		}));
+
+		// check operator can transfer every kitty of the owner
+		assert_ok!(KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, CHARLIE));
+		assert_ok!(KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_2, BOB));
+
+		// check operator is only valid for the owner's kitties
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotApproved
+		);
+	})
+}
+
+#[test]
+fn test_operator_expires_and_removed() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::remove_operator(RuntimeOrigin::signed(ALICE), BOB),
+			Error::<Test>::NoOperator
+		);
+
+		assert_ok!(KittiesV2::set_operator(RuntimeOrigin::signed(ALICE), BOB, Some(5)));
+		run_to_block(5);
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotApproved
+		);
+
+		assert_ok!(KittiesV2::set_operator(RuntimeOrigin::signed(ALICE), BOB, None));
+		assert_ok!(KittiesV2::remove_operator(RuntimeOrigin::signed(ALICE), BOB));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::OperatorRemoved {
+			owner: ALICE,
+			operator: BOB,
+		}));
+		assert_noop!(
+			KittiesV2::transfer_from(RuntimeOrigin::signed(BOB), KITTY_1, BOB),
+			Error::<Test>::NotApproved
+		);
+	})
+}
+
+#[test]
+fn test_offer_rental_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::offer_rental(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 0),
+			Error::<Test>::InvalidRentalDuration
+		);
+
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_eq!(<RentalOffers<Test>>::get(KITTY_1), Some(RentalTerms { fee: 100, duration: 10 }));
+
+		assert_ok!(KittiesV2::cancel_rental_offer(RuntimeOrigin::signed(ALICE), KITTY_1));
+		assert_eq!(<RentalOffers<Test>>::get(KITTY_1), None);
+		assert_noop!(
+			KittiesV2::cancel_rental_offer(RuntimeOrigin::signed(ALICE), KITTY_1),
+			Error::<Test>::NoRentalOffer
+		);
+	})
+}
+
+#[test]
+fn test_rent_kitty_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100),
+			Error::<Test>::NoRentalOffer
+		);
+
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_noop!(
+			KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 99),
+			Error::<Test>::BidPriceTooLow
+		);
+		assert_noop!(
+			KittiesV2::rent_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, 100),
+			Error::<Test>::TransferToSelf
+		);
+
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::Rented {
+			kitty: KITTY_1,
+			owner: ALICE,
+			borrower: BOB,
+			fee: 100,
+			expiry: 11,
+		}));
+
+		// check fee is paid and ownership stays
+		assert_eq!(Balances::free_balance(ALICE), 10_100);
+		assert_eq!(Balances::free_balance(BOB), 9_900);
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, ALICE);
+		assert_eq!(<Rentals<Test>>::get(KITTY_1), Some(Rental { borrower: BOB, expiry: 11 }));
+
+		assert_noop!(
+			KittiesV2::rent_kitty(RuntimeOrigin::signed(CHARLIE), KITTY_1, 100),
+			Error::<Test>::KittyRented
+		);
+	})
+}
+
+#[test]
+fn test_rented_kitty_is_locked() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+
+		assert_noop!(
+			KittiesV2::transfer(RuntimeOrigin::signed(ALICE), CHARLIE, KITTY_1),
+			Error::<Test>::KittyRented
+		);
+		assert_noop!(
+			KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(100)),
+			Error::<Test>::KittyRented
+		);
+		assert_noop!(
+			KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10),
+			Error::<Test>::KittyRented
+		);
+		assert_noop!(
+			KittiesV2::transfer(RuntimeOrigin::signed(BOB), CHARLIE, KITTY_1),
+			Error::<Test>::NotOwner
+		);
+	})
+}
+
+#[test]
+fn test_borrower_holds_breeding_rights() {
+	new_test_ext().execute_with(|| {
+		insert_kitty([5; 16], None);
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_2));
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+
+		// check owner lost the breeding rights
+		assert_noop!(
+			KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, [5; 16]),
+			Error::<Test>::NotOwner
+		);
+
+		// check borrower can breed and owns the newborn
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2));
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).len(), 2);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).len(), 2);
+	})
+}
+
+#[test]
+fn test_rental_ends() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+
+		run_to_block(10);
+		assert!(<Rentals<Test>>::contains_key(KITTY_1));
+
+		run_to_block(11);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::RentalEnded {
+			kitty: KITTY_1,
+			borrower: BOB,
+		}));
+		assert!(!<Rentals<Test>>::contains_key(KITTY_1));
+		assert_eq!(<RentalsExpiring<Test>>::get(11).len(), 0);
+
+		// check owner can trade the kitty again and rental terms are cleared
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), CHARLIE, KITTY_1));
+		assert_eq!(<RentalOffers<Test>>::get(KITTY_1), None);
+	})
+}
+
+#[test]
+fn test_mint_records_royalty() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			<Royalties<Test>>::get(KITTY_1),
+			Some(Royalty { creator: ALICE, rate: CreatorRoyalty::get() })
+		);
+
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let kitty = <KittiesOwned<Test>>::get(BOB)[0];
+		assert_eq!(<Royalties<Test>>::get(kitty).unwrap().creator, BOB);
+	})
+}
+
+#[test]
+fn test_buy_kitty_pays_fee_and_royalty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_1));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(BOB), KITTY_1, Some(1_000)));
+
+		assert_noop!(
+			KittiesV2::buy_kitty(RuntimeOrigin::signed(CHARLIE), KITTY_1, 999),
+			Error::<Test>::BidPriceTooLow
+		);
+		assert_ok!(KittiesV2::buy_kitty(RuntimeOrigin::signed(CHARLIE), KITTY_1, 1_000));
+		System::assert_has_event(RuntimeEvent::KittiesV2(Event::Sold {
+			seller: BOB,
+			buyer: CHARLIE,
+			kitty: KITTY_1,
+			price: 1_000,
+			seller_proceeds: 850,
+			marketplace_fee: 100,
+			royalty: Some((ALICE, 50)),
+		}));
+
+		// check the price is split
+		assert_eq!(Balances::free_balance(CHARLIE), 9_000);
+		assert_eq!(Balances::free_balance(BOB), 10_850);
+		assert_eq!(Balances::free_balance(ALICE), 10_050);
+		assert_eq!(Balances::free_balance(FEE_ACCOUNT), 10_100);
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, CHARLIE);
+	})
+}
+
+#[test]
+fn test_creator_selling_keeps_royalty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(1_000)));
+		assert_ok!(KittiesV2::buy_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 1_000));
+		System::assert_has_event(RuntimeEvent::KittiesV2(Event::Sold {
+			seller: ALICE,
+			buyer: BOB,
+			kitty: KITTY_1,
+			price: 1_000,
+			seller_proceeds: 900,
+			marketplace_fee: 100,
+			royalty: None,
+		}));
+		assert_eq!(Balances::free_balance(ALICE), 10_900);
+		assert_eq!(Balances::free_balance(BOB), 9_000);
+	})
+}
+
+#[test]
+fn test_decode_dna_dominance() {
+	use crate::genetics::{self, Color, Pattern, Rarity};
+
+	// color alleles Black (0) and White (4), Black dominates
+	let mut dna = [0u8; 16];
+	dna[genetics::COLOR_LOCUS] = 0;
+	dna[genetics::COLOR_LOCUS + 1] = 4;
+	// both pattern alleles Colorpoint, the recessive trait shows
+	dna[genetics::PATTERN_LOCUS] = 3;
+	dna[genetics::PATTERN_LOCUS + 1] = 7;
+	// rarity alleles Rare and Legendary
+	dna[genetics::RARITY_LOCUS] = 230;
+	dna[genetics::RARITY_LOCUS + 1] = 255;
+
+	let traits = genetics::decode(&dna);
+	assert_eq!(traits.gender, Gender::Male);
+	assert_eq!(traits.color.alleles, (Color::Black, Color::White));
+	assert_eq!(traits.color.expressed, Color::Black);
+	assert!(traits.color.is_carrier());
+	assert_eq!(traits.pattern.expressed, Pattern::Colorpoint);
+	assert!(!traits.pattern.is_carrier());
+	assert_eq!(traits.rarity.expressed, Rarity::Rare);
+}
+
+#[test]
+fn test_inherit_takes_one_allele_from_each_parent() {
+	use crate::genetics;
+
+	let parent_1 = [10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25];
+	let parent_2 = [30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45];
+
+	// even random bytes pick the first option
+	let dna = genetics::inherit(&parent_1, &parent_2, &[0; 16]);
+	assert_eq!(dna[0], 0);
+	assert_eq!(dna[genetics::COLOR_LOCUS], 11);
+	assert_eq!(dna[genetics::COLOR_LOCUS + 1], 31);
+	assert_eq!(dna[9..], parent_1[9..]);
+
+	// odd random bytes pick the second option
+	let dna = genetics::inherit(&parent_1, &parent_2, &[1; 16]);
+	assert_eq!(dna[0], 1);
+	assert_eq!(dna[genetics::COLOR_LOCUS], 12);
+	assert_eq!(dna[genetics::COLOR_LOCUS + 1], 32);
+	assert_eq!(dna[9..], parent_2[9..]);
+}
+
+#[test]
+fn test_breed_kitty_inherits_traits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+
+		let child = <KittiesOwned<Test>>::get(ALICE)[2];
+		let kitty = <Kitties<Test>>::get(child).unwrap();
+		let traits = KittiesV2::traits(child).unwrap();
+		assert_eq!(traits.gender, kitty.gender);
+
+		// every gene has an allele of KITTY_1 first and of KITTY_2 second
+		for locus in [1, 3, 5, 7] {
+			assert_eq!(child[locus], 1);
+			assert_eq!(child[locus + 1], 2);
+		}
+
+		assert_eq!(KittiesV2::traits([9; 16]), None);
+	})
+}
+
+#[test]
+fn test_breed_kitty_without_mutation() {
+	new_test_ext().execute_with(|| {
+		MutationRate::set(Perbill::from_percent(0));
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+
+		// every locus but the gender comes from a parent
+		let child = <KittiesOwned<Test>>::get(ALICE)[2];
+		assert!(child[1..].iter().all(|byte| *byte == 1 || *byte == 2));
+		assert!(!System::events()
+			.iter()
+			.any(|record| matches!(record.event, RuntimeEvent::KittiesV2(Event::Mutated { .. }))));
+	})
+}
+
+#[test]
+fn test_breed_kitty_mutation_work() {
+	new_test_ext().execute_with(|| {
+		MutationRate::set(Perbill::from_percent(100));
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+
+		// every locus but the gender mutates
+		let child = <KittiesOwned<Test>>::get(ALICE)[2];
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::Mutated {
+			kitty: child,
+			loci: (1..16).collect(),
+		}));
+		assert_eq!(<Kitties<Test>>::get(child).unwrap().gender, KittiesV2::traits(child).unwrap().gender);
+	})
+}
+
+#[test]
+fn test_breed_kitty_is_deterministic() {
+	let breed = |seed: H256| {
+		new_test_ext().execute_with(|| {
+			RandomSeed::set(seed);
+			MutationRate::set(Perbill::from_percent(50));
+			assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+			<KittiesOwned<Test>>::get(ALICE)[2]
+		})
+	};
+
+	// the same randomness breeds the same kitty
+	assert_eq!(breed(H256::repeat_byte(7)), breed(H256::repeat_byte(7)));
+	assert_ne!(breed(H256::repeat_byte(7)), breed(H256::repeat_byte(8)));
+}
+
+#[test]
+fn test_mutate_is_deterministic() {
+	use crate::genetics;
+
+	let mut dna = [1; 16];
+	assert_eq!(genetics::mutate(&mut dna, Perbill::from_percent(0), &[3; 16]), Vec::<u8>::new());
+	assert_eq!(dna, [1; 16]);
+
+	let mut dna_1 = [1; 16];
+	let mut dna_2 = [1; 16];
+	let mutated = genetics::mutate(&mut dna_1, Perbill::from_percent(50), &[3; 16]);
+	assert_eq!(genetics::mutate(&mut dna_2, Perbill::from_percent(50), &[3; 16]), mutated);
+	assert_eq!(dna_1, dna_2);
+
+	// exactly the reported loci changed
+	for locus in 0..16u8 {
+		assert_eq!(dna_1[locus as usize] != 1, mutated.contains(&locus));
+	}
+}
+
+frame_support::parameter_types! {
+	pub const LegacyKittiesName: &'static str = "Kitties";
+	pub const LegacyKittiesMyselfName: &'static str = "KittiesMyself";
+}
+
+type MigrateToV3 =
+	crate::migrations::unify::MigrateToV3<Test, LegacyKittiesName, LegacyKittiesMyselfName, u64>;
+
+#[test]
+fn test_move_from_kitties() {
+	use crate::migrations::unify::KittiesKitty;
+	use codec::Encode;
+	use frame_support::{
+		storage::migration::{get_storage_value, put_storage_value},
+		traits::{OnRuntimeUpgrade, StorageVersion},
+		StorageHasher, Twox64Concat,
+	};
+
+	new_test_ext().execute_with(|| {
+		let put_kitty = |key: H256, dna: [u8; 16], price: Option<u128>, owner: u64| {
+			let kitty = KittiesKitty::<Test> { dna, price, gender: Gender::Female, owner };
+			put_storage_value(b"Kitties", b"Kitties", &Twox64Concat::hash(&key.encode()), kitty);
+		};
+		// the second kitty has the DNA of KITTY_1, the third an owner who can't own more
+		put_kitty(H256::repeat_byte(1), [7; 16], Some(100), BOB);
+		put_kitty(H256::repeat_byte(2), KITTY_1, None, BOB);
+		put_kitty(H256::repeat_byte(3), [8; 16], None, DAVE);
+		let owned = vec![H256::repeat_byte(1), H256::repeat_byte(2)];
+		put_storage_value(b"Kitties", b"KittiesOwned", &Twox64Concat::hash(&BOB.encode()), owned);
+		put_storage_value(b"Kitties", b"KittiesOwned", &Twox64Concat::hash(&DAVE.encode()), vec![H256::repeat_byte(3)]);
+		put_storage_value(b"Kitties", b"KittyCount", &[], 3u64);
+		<KittiesOwned<Test>>::mutate(DAVE, |owned| (0..10).for_each(|byte| owned.try_push([100 + byte; 16]).unwrap()));
+
+		StorageVersion::new(2).put::<KittiesV2>();
+		MigrateToV3::on_runtime_upgrade();
+		assert_eq!(StorageVersion::get::<KittiesV2>(), 3);
+
+		let kitty = <Kitties<Test>>::get([7; 16]).unwrap();
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(kitty.price, Some(100));
+		assert_eq!(<ForSale<Test>>::get(KittiesV2::price_bucket(100), [7; 16]), Some(100));
+		assert_eq!(kitty.gender, Gender::Female);
+		assert_eq!(kitty.generation, 0);
+
+		// the taken DNA is derived again
+		let rederived = sp_io::hashing::blake2_128(&KITTY_1);
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, ALICE);
+		assert_eq!(<Kitties<Test>>::get(rederived).unwrap().owner, BOB);
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).into_inner(), vec![[7; 16], rederived]);
+		assert_eq!(<CountForKitties<Test>>::get(), 4);
+
+		// the kitty left behind is still counted and indexed in the other pallet
+		assert_eq!(<Kitties<Test>>::get([8; 16]), None);
+		assert_eq!(
+			get_storage_value::<Vec<H256>>(b"Kitties", b"KittiesOwned", &Twox64Concat::hash(&BOB.encode())),
+			None
+		);
+		assert_eq!(
+			get_storage_value::<Vec<H256>>(b"Kitties", b"KittiesOwned", &Twox64Concat::hash(&DAVE.encode())),
+			Some(vec![H256::repeat_byte(3)])
+		);
+		assert_eq!(get_storage_value::<u64>(b"Kitties", b"KittyCount", &[]), Some(1));
+
+		// later upgrades leave the other pallet alone
+		<KittiesOwned<Test>>::remove(DAVE);
+		MigrateToV3::on_runtime_upgrade();
+		assert_eq!(<Kitties<Test>>::get([8; 16]), None);
+		assert_eq!(get_storage_value::<u64>(b"Kitties", b"KittyCount", &[]), Some(1));
+	})
+}
+
+#[test]
+fn test_move_from_kitties_myself() {
+	use crate::migrations::unify::KittiesMyselfKitty;
+	use codec::Encode;
+	use frame_support::{
+		storage::migration::{get_storage_value, put_storage_value},
+		traits::{OnRuntimeUpgrade, StorageVersion},
+		Blake2_128Concat, StorageHasher,
+	};
+
+	new_test_ext().execute_with(|| {
+		let put_kitty = |dna: H256, price: u128| {
+			let kitty = KittiesMyselfKitty::<Test, u64> {
+				dna,
+				owner: CHARLIE,
+				price,
+				gender: Gender::Male,
+				created_date: 0,
+			};
+			put_storage_value(b"KittiesMyself", b"Kitties", &Blake2_128Concat::hash(&dna.encode()), kitty);
+		};
+		put_kitty(H256::repeat_byte(5), 0);
+		put_kitty(H256::repeat_byte(6), 50);
+		let owned = vec![H256::repeat_byte(5), H256::repeat_byte(6)];
+		put_storage_value(b"KittiesMyself", b"KittiesOwned", &Blake2_128Concat::hash(&CHARLIE.encode()), owned);
+		put_storage_value(b"KittiesMyself", b"KittiesTotal", &[], 2u64);
+
+		StorageVersion::new(2).put::<KittiesV2>();
+		MigrateToV3::on_runtime_upgrade();
+
+		// the leading bytes of the hash become the DNA and free kitties are not for sale
+		assert_eq!(<Kitties<Test>>::get([5; 16]).unwrap().price, None);
+		assert_eq!(<Kitties<Test>>::get([6; 16]).unwrap().price, Some(50));
+		assert_eq!(<KittiesOwned<Test>>::get(CHARLIE).len(), 2);
+		assert_eq!(<CountForKitties<Test>>::get(), 4);
+
+		// nothing is left in the other pallet
+		assert_eq!(
+			get_storage_value::<Vec<H256>>(b"KittiesMyself", b"KittiesOwned", &Blake2_128Concat::hash(&CHARLIE.encode())),
+			None
+		);
+		assert_eq!(get_storage_value::<u64>(b"KittiesMyself", b"KittiesTotal", &[]), None);
+	})
+}
+
+#[test]
+fn test_move_from_kitties_myself_v1() {
+	use crate::migrations::unify::KittiesMyselfKittyV1;
+	use codec::Encode;
+	use frame_support::{
+		storage::migration::{get_storage_value, put_storage_value},
+		traits::{OnRuntimeUpgrade, StorageVersion},
+		Blake2_128Concat, StorageHasher,
+	};
+
+	new_test_ext().execute_with(|| {
+		let put_kitty = |dna: H256, price: Option<u128>, for_sale: bool| {
+			let kitty = KittiesMyselfKittyV1::<Test, u64> {
+				dna,
+				owner: CHARLIE,
+				price,
+				for_sale,
+				gender: Gender::Male,
+				created_date: 0,
+			};
+			put_storage_value(b"KittiesMyself", b"Kitties", &Blake2_128Concat::hash(&dna.encode()), kitty);
+		};
+		put_kitty(H256::repeat_byte(5), Some(40), false);
+		put_kitty(H256::repeat_byte(6), Some(50), true);
+		let owned = vec![H256::repeat_byte(5), H256::repeat_byte(6)];
+		put_storage_value(b"KittiesMyself", b"KittiesOwned", &Blake2_128Concat::hash(&CHARLIE.encode()), owned);
+		put_storage_value(b"KittiesMyself", b"KittiesTotal", &[], 2u64);
+		put_storage_value(b"KittiesMyself", b":__STORAGE_VERSION__:", &[], StorageVersion::new(1));
+		let retired_key = Blake2_128Concat::hash(&H256::repeat_byte(5).encode());
+		put_storage_value(b"KittiesMyself", b"RetiredKitties", &retired_key, 0u64);
+
+		StorageVersion::new(2).put::<KittiesV2>();
+		MigrateToV3::on_runtime_upgrade();
+
+		// kitties keep their layout and are only for sale when flagged so
+		let kitty = <Kitties<Test>>::get([5; 16]).unwrap();
+		assert_eq!((kitty.owner, kitty.gender, kitty.price), (CHARLIE, Gender::Male, None));
+		assert_eq!(<Kitties<Test>>::get([6; 16]).unwrap().price, Some(50));
+		assert_eq!(<ForSale<Test>>::get(KittiesV2::price_bucket(50), [6; 16]), Some(50));
+		assert_eq!(<CountForKitties<Test>>::get(), 4);
+		assert_eq!(get_storage_value::<u64>(b"KittiesMyself", b"KittiesTotal", &[]), None);
+
+		// the retired kitty moved off sale and its retirement is gone from the other pallet
+		assert_eq!(get_storage_value::<u64>(b"KittiesMyself", b"RetiredKitties", &retired_key), None);
+	})
+}
+
+#[test]
+fn test_burn_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(500)));
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::approve(RuntimeOrigin::signed(ALICE), KITTY_1, BOB, None));
+		assert_eq!(CountForKitties::<Test>::get(), 2);
+
+		assert_ok!(KittiesV2::burn(RuntimeOrigin::signed(ALICE), KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::Burned { kitty: KITTY_1, owner: ALICE }));
+
+		// check kitty is gone with everything attached to it
+		assert_eq!(<Kitties<Test>>::get(KITTY_1), None);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).into_inner(), vec![KITTY_2]);
+		assert_eq!(CountForKitties::<Test>::get(), 1);
+		assert_eq!(<Offers<Test>>::get(KITTY_1).len(), 0);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(<Approvals<Test>>::get(KITTY_1), None);
+		assert_eq!(<Royalties<Test>>::get(KITTY_1), None);
+	})
+}
+
+#[test]
+fn test_burn_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(KittiesV2::burn(RuntimeOrigin::signed(ALICE), [9; 16]), Error::<Test>::NoKitty);
+		assert_noop!(KittiesV2::burn(RuntimeOrigin::signed(BOB), KITTY_1), Error::<Test>::NotOwner);
+
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_noop!(KittiesV2::burn(RuntimeOrigin::signed(ALICE), KITTY_1), Error::<Test>::KittyInAuction);
+
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_2, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_2, 100));
+		assert_noop!(KittiesV2::burn(RuntimeOrigin::signed(ALICE), KITTY_2), Error::<Test>::KittyRented);
+
+		// only root can force a burn
+		assert_noop!(
+			KittiesV2::force_burn(RuntimeOrigin::signed(ALICE), KITTY_1),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	})
+}
+
+#[test]
+fn test_force_burn_ends_auction_and_rental() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::place_bid(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_2, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(CHARLIE), KITTY_2, 100));
+
+		assert_ok!(KittiesV2::force_burn(RuntimeOrigin::root(), KITTY_1));
+		assert_ok!(KittiesV2::force_burn(RuntimeOrigin::root(), KITTY_2));
+		assert_eq!(CountForKitties::<Test>::get(), 0);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).len(), 0);
+
+		// check the bid is refunded and nothing is left to settle
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(<Auctions<Test>>::get(KITTY_1), None);
+		assert_eq!(<AuctionsEnding<Test>>::get(10).len(), 0);
+		assert_eq!(<Rentals<Test>>::get(KITTY_2), None);
+		assert_eq!(<RentalsExpiring<Test>>::get(11).len(), 0);
+	})
+}
+
+#[test]
+fn test_nonfungibles_burn() {
+	use frame_support::traits::tokens::nonfungibles::Mutate;
+
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_1, Some(&BOB)),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_1, Some(&ALICE)));
+		assert_ok!(<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_2, None));
+		assert_eq!(CountForKitties::<Test>::get(), 0);
+	})
+}
+
+#[test]
+fn test_nonfungibles_burn_locked_fail() {
+	use frame_support::traits::tokens::nonfungibles::Mutate;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_noop!(
+			<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_1, Some(&ALICE)),
+			Error::<Test>::KittyInAuction
+		);
+		assert_noop!(<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_1, None), Error::<Test>::KittyInAuction);
+
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_2, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_2, 100));
+		assert_noop!(
+			<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_2, Some(&ALICE)),
+			Error::<Test>::KittyRented
+		);
+		assert_noop!(<KittiesV2 as Mutate<u64>>::burn(&(), &KITTY_2, None), Error::<Test>::KittyRented);
+	})
+}
+
+#[test]
+fn test_mint_reserves_deposit() {
+	new_test_ext().execute_with(|| {
+		// check genesis kitties are free
+		assert_eq!(<KittyDeposits<Test>>::get(KITTY_1), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let kitty = <KittiesOwned<Test>>::get(BOB)[0];
+		assert_eq!(<KittyDeposits<Test>>::get(kitty), Some(KittyDeposit::get()));
+		assert_eq!(Balances::reserved_balance(BOB), 100);
+
+		// check bred kitties are paid by the breeder
+		assert_ok!(KittiesV2::breed_kitty(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2));
+		assert_eq!(Balances::reserved_balance(ALICE), 100);
+
+		// check minting fails without the deposit
+		Balances::make_free_balance_be(&CHARLIE, 50);
+		assert!(KittiesV2::create_kitty(RuntimeOrigin::signed(CHARLIE)).is_err());
+		assert_eq!(<KittiesOwned<Test>>::get(CHARLIE).len(), 0);
+	})
+}
+
+#[test]
+fn test_deposit_follows_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let kitty = <KittiesOwned<Test>>::get(BOB)[0];
+
+		// check the previous owner gets the deposit back and the new owner pays it
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(BOB), CHARLIE, kitty));
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(Balances::free_balance(BOB), 10_000);
+		assert_eq!(Balances::reserved_balance(CHARLIE), 100);
+		assert_eq!(Balances::free_balance(CHARLIE), 9_900);
+
+		// check burning gives the deposit back
+		assert_ok!(KittiesV2::burn(RuntimeOrigin::signed(CHARLIE), kitty));
+		assert_eq!(Balances::reserved_balance(CHARLIE), 0);
+		assert_eq!(Balances::free_balance(CHARLIE), 10_000);
+		assert_eq!(<KittyDeposits<Test>>::get(kitty), None);
+	})
+}
+
+#[test]
+fn test_buy_kitty_moves_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let kitty = <KittiesOwned<Test>>::get(BOB)[0];
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(BOB), CHARLIE, kitty));
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(CHARLIE), kitty, Some(1_000)));
+		assert_ok!(KittiesV2::buy_kitty(RuntimeOrigin::signed(DAVE), kitty, 1_000));
+
+		// the seller gets the deposit back on top of the proceeds
+		assert_eq!(Balances::reserved_balance(CHARLIE), 0);
+		assert_eq!(Balances::free_balance(CHARLIE), 10_850);
+		// the buyer pays the price and a deposit of their own
+		assert_eq!(Balances::reserved_balance(DAVE), 100);
+		assert_eq!(Balances::free_balance(DAVE), 8_900);
+		assert_eq!(<KittyDeposits<Test>>::get(kitty), Some(100));
+	})
+}
+
+#[test]
+fn test_transfer_without_deposit_fail() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let kitty = <KittiesOwned<Test>>::get(BOB)[0];
+
+		// account 5 has no balance to reserve the deposit from
+		assert_noop!(
+			KittiesV2::transfer(RuntimeOrigin::signed(BOB), 5, kitty),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+	})
+}
+
+#[test]
+fn test_transfer_batch_work() {
+	new_test_ext().execute_with(|| {
+		let kitty_ids = vec![KITTY_1, KITTY_2].try_into().unwrap();
+		assert_ok!(KittiesV2::transfer_batch(RuntimeOrigin::signed(ALICE), BOB, kitty_ids));
+
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).len(), 0);
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).into_inner(), vec![KITTY_1, KITTY_2]);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::Transferred { from: ALICE, to: BOB, kitty: KITTY_2 }));
+	})
+}
+
+#[test]
+fn test_transfer_batch_is_atomic() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+		let bob_kitty = <KittiesOwned<Test>>::get(BOB)[0];
+
+		// the last kitty is not owned by the caller, so nothing is transferred
+		let kitty_ids = vec![KITTY_1, KITTY_2, bob_kitty].try_into().unwrap();
+		assert_noop!(
+			KittiesV2::transfer_batch(RuntimeOrigin::signed(ALICE), CHARLIE, kitty_ids),
+			Error::<Test>::NotOwner
+		);
+
+		// the same kitty can't be transferred twice
+		let kitty_ids = vec![KITTY_1, KITTY_1].try_into().unwrap();
+		assert_noop!(
+			KittiesV2::transfer_batch(RuntimeOrigin::signed(ALICE), CHARLIE, kitty_ids),
+			Error::<Test>::NotOwner
+		);
+	})
+}
+
+#[test]
+fn test_set_price_batch_work() {
+	new_test_ext().execute_with(|| {
+		let prices = vec![(KITTY_1, Some(100)), (KITTY_2, Some(200))].try_into().unwrap();
+		assert_ok!(KittiesV2::set_price_batch(RuntimeOrigin::signed(ALICE), prices));
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().price, Some(100));
+		assert_eq!(<Kitties<Test>>::get(KITTY_2).unwrap().price, Some(200));
+
+		// an auctioned kitty fails the whole batch
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_2, 100, 10));
+		let prices = vec![(KITTY_1, None), (KITTY_2, Some(300))].try_into().unwrap();
+		assert_noop!(
+			KittiesV2::set_price_batch(RuntimeOrigin::signed(ALICE), prices),
+			Error::<Test>::KittyInAuction
+		);
+	})
+}
+
+#[test]
+fn test_buy_batch_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(1_000)));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_2, Some(2_000)));
+
+		let purchases = vec![(KITTY_1, 1_000), (KITTY_2, 2_000)].try_into().unwrap();
+		assert_ok!(KittiesV2::buy_batch(RuntimeOrigin::signed(BOB), purchases));
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).into_inner(), vec![KITTY_1, KITTY_2]);
+		assert_eq!(Balances::free_balance(BOB), 7_000);
+	})
+}
+
+#[test]
+fn test_buy_batch_is_atomic() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(1_000)));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_2, Some(2_000)));
+
+		// the second limit price is too low, so the first kitty is not bought either
+		let purchases = vec![(KITTY_1, 1_000), (KITTY_2, 1_999)].try_into().unwrap();
+		assert_noop!(
+			KittiesV2::buy_batch(RuntimeOrigin::signed(BOB), purchases),
+			Error::<Test>::BidPriceTooLow
+		);
+	})
+}
+
+#[test]
+fn test_set_metadata_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom".to_vec(), b"ipfs://tom".to_vec()));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::MetadataSet { kitty: KITTY_1 }));
+
+		let metadata = <Metadata<Test>>::get(KITTY_1).unwrap();
+		assert_eq!(metadata.name.into_inner(), b"Tom".to_vec());
+		assert_eq!(metadata.uri.into_inner(), b"ipfs://tom".to_vec());
+		assert_eq!(Balances::reserved_balance(ALICE), 50);
+
+		// check renaming keeps the same deposit
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Felix".to_vec(), vec![]));
+		assert_eq!(<Metadata<Test>>::get(KITTY_1).unwrap().name.into_inner(), b"Felix".to_vec());
+		assert_eq!(Balances::reserved_balance(ALICE), 50);
+
+		assert_ok!(KittiesV2::clear_metadata(RuntimeOrigin::signed(ALICE), KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::MetadataCleared { kitty: KITTY_1 }));
+		assert_eq!(<Metadata<Test>>::get(KITTY_1), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+	})
+}
+
+#[test]
+fn test_set_metadata_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::set_metadata(RuntimeOrigin::signed(BOB), KITTY_1, b"Tom".to_vec(), vec![]),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"To".to_vec(), vec![]),
+			Error::<Test>::NameTooShort
+		);
+		assert_noop!(
+			KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom Kitten".to_vec(), vec![]),
+			Error::<Test>::NameTooLong
+		);
+		assert_noop!(
+			KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom".to_vec(), vec![0; 17]),
+			Error::<Test>::UriTooLong
+		);
+		assert_noop!(
+			KittiesV2::clear_metadata(RuntimeOrigin::signed(ALICE), KITTY_1),
+			Error::<Test>::NoMetadata
+		);
+	})
+}
+
+#[test]
+fn test_transfer_clears_metadata() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom".to_vec(), vec![]));
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_1));
+
+		assert_eq!(<Metadata<Test>>::get(KITTY_1), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+	})
+}
+
+#[test]
+fn test_transfer_keeps_metadata() {
+	new_test_ext().execute_with(|| {
+		ClearMetadataOnTransfer::set(false);
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom".to_vec(), vec![]));
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_1));
+
+		// check the previous owner still holds the deposit until the new owner renames the kitty
+		assert_eq!(<Metadata<Test>>::get(KITTY_1).unwrap().depositor, ALICE);
+		assert_eq!(Balances::reserved_balance(ALICE), 50);
+
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(BOB), KITTY_1, b"Felix".to_vec(), vec![]));
+		assert_eq!(<Metadata<Test>>::get(KITTY_1).unwrap().depositor, BOB);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::reserved_balance(BOB), 50);
+	})
+}
+
+#[test]
+fn test_kill_metadata_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom".to_vec(), vec![]));
+
+		assert_noop!(
+			KittiesV2::kill_metadata(RuntimeOrigin::signed(BOB), KITTY_1),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(KittiesV2::kill_metadata(RuntimeOrigin::root(), KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::MetadataKilled { kitty: KITTY_1, deposit: 50 }));
+
+		// check the deposit is slashed
+		assert_eq!(<Metadata<Test>>::get(KITTY_1), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 9_950);
+		assert_noop!(KittiesV2::kill_metadata(RuntimeOrigin::root(), KITTY_1), Error::<Test>::NoMetadata);
+	})
+}
+
+#[test]
+fn test_kitty_queries() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(KittiesV2::kitty_count(), 2);
+		assert_eq!(KittiesV2::kitties_by_owner(&ALICE), vec![KITTY_1, KITTY_2]);
+		assert_eq!(KittiesV2::kitties_by_owner(&BOB), Vec::<[u8; 16]>::new());
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(100)));
+		assert_ok!(KittiesV2::set_metadata(RuntimeOrigin::signed(ALICE), KITTY_1, b"Tom".to_vec(), b"ipfs://tom".to_vec()));
+		let details = KittiesV2::kitty_details(KITTY_1).unwrap();
+		assert_eq!(details.owner, ALICE);
+		assert_eq!(details.price, Some(100));
+		assert_eq!(details.name, Some(b"Tom".to_vec()));
+		assert_eq!(details.uri, Some(b"ipfs://tom".to_vec()));
+		assert_eq!(details.gender, KittiesV2::traits(KITTY_1).unwrap().gender);
+		assert_eq!(KittiesV2::kitty_details(KITTY_2).unwrap().name, None);
+		assert_eq!(KittiesV2::kitty_details([9; 16]), None);
+	})
+}
+
+#[test]
+#[should_panic(expected = "Genesis kitty gender doesn't match its DNA")]
+fn test_genesis_gender_mismatch_fail() {
+	use frame_support::traits::GenesisBuild;
+
+	// KITTY_1 has an odd leading byte, so it is female
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	crate::GenesisConfig::<Test> { kitties_v2: vec![(ALICE, KITTY_1, Gender::Male)] }.assimilate_storage(&mut t).unwrap();
+}
+
+#[test]
+fn test_rent_listed_kitty_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(500)));
+		assert_ok!(KittiesV2::offer_rental(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_ok!(KittiesV2::rent_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+
+		// check the rented kitty is taken off the fixed price market
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().price, None);
+		assert_eq!(<ForSale<Test>>::get(KittiesV2::price_bucket(500), KITTY_1), None);
+		assert_eq!(KittiesV2::kitties_for_sale(None, 10), vec![]);
+		assert_noop!(
+			KittiesV2::buy_kitty(RuntimeOrigin::signed(CHARLIE), KITTY_1, 500),
+			Error::<Test>::KittyRented
+		);
+	})
+}
+
+#[test]
+fn test_kitties_for_sale_pagination() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(KittiesV2::kitties_for_sale(None, 10), vec![]);
+
+		// the kitties of BOB are the cheapest, two of them at the same price
+		for _ in 0..3 {
+			assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(BOB)));
+			run_to_block(System::block_number() + 1);
+		}
+		let mut bob_kitties = <KittiesOwned<Test>>::get(BOB).into_inner();
+		bob_kitties.sort();
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(1_000)));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_2, Some(300)));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(BOB), bob_kitties[0], Some(200)));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(BOB), bob_kitties[1], Some(200)));
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(BOB), bob_kitties[2], Some(5)));
+
+		let expected = vec![
+			(bob_kitties[2], 5),
+			(bob_kitties[0], 200),
+			(bob_kitties[1], 200),
+			(KITTY_2, 300),
+			(KITTY_1, 1_000),
+		];
+		assert_eq!(KittiesV2::kitties_for_sale(None, 10), expected);
+
+		// check each page continues after the last kitty of the previous one
+		let first = KittiesV2::kitties_for_sale(None, 2);
+		assert_eq!(first, expected[..2].to_vec());
+		let (kitty, price) = first[1];
+		let second = KittiesV2::kitties_for_sale(Some((price, kitty)), 2);
+		assert_eq!(second, expected[2..4].to_vec());
+		let (kitty, price) = second[1];
+		assert_eq!(KittiesV2::kitties_for_sale(Some((price, kitty)), 2), expected[4..].to_vec());
+		assert_eq!(KittiesV2::kitties_for_sale(Some((1_000, KITTY_1)), 2), vec![]);
+		assert_eq!(KittiesV2::kitties_for_sale(None, 0), vec![]);
+	})
+}
+
+#[test]
+fn test_for_sale_index() {
+	new_test_ext().execute_with(|| {
+		let listed = || <ForSale<Test>>::iter().map(|(_, kitty, price)| (kitty, price)).collect::<Vec<_>>();
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(100)));
+		assert_eq!(<ForSale<Test>>::get(KittiesV2::price_bucket(100), KITTY_1), Some(100));
+
+		// check a new price moves the kitty to another bucket
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(1_000)));
+		assert_eq!(listed(), vec![(KITTY_1, 1_000)]);
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, None));
+		assert_eq!(listed(), vec![]);
+
+		// check sold, auctioned, transferred and burned kitties leave the index
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(100)));
+		assert_ok!(KittiesV2::buy_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 100));
+		assert_eq!(listed(), vec![]);
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_2, Some(100)));
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_2, 100, 10));
+		assert_eq!(listed(), vec![]);
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(BOB), KITTY_1, Some(100)));
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(BOB), CHARLIE, KITTY_1));
+		assert_eq!(listed(), vec![]);
+
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(CHARLIE), KITTY_1, Some(100)));
+		assert_ok!(KittiesV2::burn(RuntimeOrigin::signed(CHARLIE), KITTY_1));
+		assert_eq!(listed(), vec![]);
+	})
+}
+
+#[test]
+fn test_accept_offer_delists_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(1_000)));
+		assert_ok!(KittiesV2::make_offer(RuntimeOrigin::signed(BOB), KITTY_1, 500, 10));
+		assert_ok!(KittiesV2::accept_offer(RuntimeOrigin::signed(ALICE), KITTY_1, BOB));
+		assert_eq!(<ForSale<Test>>::iter().count(), 0);
+	})
+}
+
+#[test]
+fn test_price_bucket() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(KittiesV2::price_bucket(0), 0);
+		assert_eq!(KittiesV2::price_bucket(1), 1);
+		assert_eq!(KittiesV2::price_bucket(255), 8);
+		assert_eq!(KittiesV2::price_bucket(256), 9);
+		assert_eq!(KittiesV2::price_bucket(u128::MAX), 128);
+	})
+}
+
+#[test]
+fn test_migrate_to_v2() {
+	use crate::migrations::v2::MigrateToV2;
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<KittiesV2>();
+		<Kitties<Test>>::mutate(KITTY_1, |kitty| kitty.as_mut().unwrap().price = Some(100));
+
+		MigrateToV2::<Test>::on_runtime_upgrade();
+
+		assert_eq!(<ForSale<Test>>::get(KittiesV2::price_bucket(100), KITTY_1), Some(100));
+		assert_eq!(<ForSale<Test>>::iter().count(), 1);
+		assert_eq!(StorageVersion::get::<KittiesV2>(), 2);
+	})
+}
+
+#[test]
+fn test_create_dutch_auction_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(500)));
+		assert_ok!(KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 1_000, 200, 8));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::DutchAuctionCreated {
+			kitty: KITTY_1,
+			start_price: 1_000,
+			floor_price: 200,
+			start: 1,
+			end: 9,
+		}));
+
+		// check kitty is taken off the fixed price market and locked
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().price, None);
+		assert_eq!(<ForSale<Test>>::iter().count(), 0);
+		assert_noop!(
+			KittiesV2::set_price(RuntimeOrigin::signed(ALICE), KITTY_1, Some(100)),
+			Error::<Test>::KittyInAuction
+		);
+		assert_noop!(
+			KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10),
+			Error::<Test>::KittyInAuction
+		);
+	})
+}
+
+#[test]
+fn test_create_dutch_auction_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::create_dutch_auction(RuntimeOrigin::signed(BOB), KITTY_1, 1_000, 200, 8),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 1_000, 200, 0),
+			Error::<Test>::InvalidDutchAuction
+		);
+		assert_noop!(
+			KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 200, 1_000, 8),
+			Error::<Test>::InvalidDutchAuction
+		);
+
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 100, 10));
+		assert_noop!(
+			KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 1_000, 200, 8),
+			Error::<Test>::KittyInAuction
+		);
+	})
+}
+
+#[test]
+fn test_dutch_auction_price_decays() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(KittiesV2::dutch_auction_price(&KITTY_1), None);
+		assert_ok!(KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 1_000, 200, 8));
+
+		assert_eq!(KittiesV2::dutch_auction_price(&KITTY_1), Some(1_000));
+		run_to_block(3);
+		assert_eq!(KittiesV2::dutch_auction_price(&KITTY_1), Some(800));
+		run_to_block(5);
+		assert_eq!(KittiesV2::dutch_auction_price(&KITTY_1), Some(600));
+		run_to_block(9);
+		assert_eq!(KittiesV2::dutch_auction_price(&KITTY_1), Some(200));
+		run_to_block(20);
+		assert_eq!(KittiesV2::dutch_auction_price(&KITTY_1), Some(200));
+	})
+}
+
+#[test]
+fn test_buy_dutch_auctioned_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 1_000, 200, 8));
+		run_to_block(5);
+
+		// the limit price still guards the buyer
+		assert_noop!(
+			KittiesV2::buy_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 599),
+			Error::<Test>::BidPriceTooLow
+		);
+
+		// check the buyer pays the current price, not the limit
+		assert_ok!(KittiesV2::buy_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 1_000));
+		System::assert_has_event(RuntimeEvent::KittiesV2(Event::Sold {
+			seller: ALICE,
+			buyer: BOB,
+			kitty: KITTY_1,
+			price: 600,
+			seller_proceeds: 540,
+			marketplace_fee: 60,
+			royalty: None,
+		}));
+		assert_eq!(Balances::free_balance(BOB), 9_400);
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, BOB);
+		assert_eq!(<DutchAuctions<Test>>::get(KITTY_1), None);
+	})
+}
+
+#[test]
+fn test_cancel_dutch_auction_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::cancel_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1),
+			Error::<Test>::NoAuction
+		);
+		assert_ok!(KittiesV2::create_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1, 1_000, 200, 8));
+		assert_noop!(
+			KittiesV2::cancel_dutch_auction(RuntimeOrigin::signed(BOB), KITTY_1),
+			Error::<Test>::NotOwner
+		);
+
+		assert_ok!(KittiesV2::cancel_dutch_auction(RuntimeOrigin::signed(ALICE), KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::DutchAuctionCancelled { kitty: KITTY_1 }));
+		assert_eq!(<DutchAuctions<Test>>::get(KITTY_1), None);
+		assert_noop!(
+			KittiesV2::buy_kitty(RuntimeOrigin::signed(BOB), KITTY_1, 1_000),
+			Error::<Test>::NotForSale
+		);
+	})
+}
+
+#[test]
+fn test_swap_kitties_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_2));
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, Some(300)));
+
+		// check the proposal is stored and the top-up reserved
+		let swap = <Swaps<Test>>::get(KITTY_1).unwrap();
+		assert_eq!(swap.proposer, ALICE);
+		assert_eq!(swap.requested, KITTY_2);
+		assert_eq!(<SwapRequests<Test>>::get(KITTY_2).into_inner(), vec![KITTY_1]);
+		assert_eq!(Balances::reserved_balance(ALICE), 300);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::SwapProposed {
+			offered: KITTY_1,
+			requested: KITTY_2,
+			proposer: ALICE,
+			top_up: Some(300),
+		}));
+
+		assert_ok!(KittiesV2::accept_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2, 300));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::Swapped {
+			offered: KITTY_1,
+			requested: KITTY_2,
+			proposer: ALICE,
+			counterparty: BOB,
+			top_up: Some(300),
+		}));
+
+		// check both kitties changed hands and the top-up was paid
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, BOB);
+		assert_eq!(<Kitties<Test>>::get(KITTY_2).unwrap().owner, ALICE);
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE).into_inner(), vec![KITTY_2]);
+		assert_eq!(<KittiesOwned<Test>>::get(BOB).into_inner(), vec![KITTY_1]);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 9_700);
+		assert_eq!(Balances::free_balance(BOB), 10_300);
+		assert_eq!(<Swaps<Test>>::get(KITTY_1), None);
+		assert_eq!(<SwapRequests<Test>>::get(KITTY_2).len(), 0);
+	})
+}
+
+#[test]
+fn test_propose_swap_fail() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			KittiesV2::propose_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2, None),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, None),
+			Error::<Test>::TransferToSelf
+		);
+		assert_noop!(
+			KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, [9; 16], None),
+			Error::<Test>::NoKitty
+		);
+
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_2));
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, None));
+		assert_noop!(
+			KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, None),
+			Error::<Test>::SwapExists
+		);
+
+		// check the top-up must be reservable
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(CHARLIE)));
+		let kitty_3 = <KittiesOwned<Test>>::get(CHARLIE)[0];
+		assert!(KittiesV2::propose_swap(RuntimeOrigin::signed(CHARLIE), kitty_3, KITTY_2, Some(20_000)).is_err());
+
+		// check a kitty may only be requested by `MaxSwapsPerKitty` proposals
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(CHARLIE), kitty_3, KITTY_2, None));
+		run_to_block(2);
+		assert_ok!(KittiesV2::create_kitty(RuntimeOrigin::signed(DAVE)));
+		let kitty_4 = <KittiesOwned<Test>>::get(DAVE)[0];
+		assert_noop!(
+			KittiesV2::propose_swap(RuntimeOrigin::signed(DAVE), kitty_4, KITTY_2, None),
+			Error::<Test>::TooManySwaps
+		);
+	})
+}
+
+#[test]
+fn test_accept_swap_fail() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_2));
+		assert_noop!(
+			KittiesV2::accept_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2, 0),
+			Error::<Test>::NoSwap
+		);
+
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, Some(300)));
+		assert_noop!(
+			KittiesV2::accept_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_1, 0),
+			Error::<Test>::NoSwap
+		);
+		assert_noop!(
+			KittiesV2::accept_swap(RuntimeOrigin::signed(CHARLIE), KITTY_1, KITTY_2, 0),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			KittiesV2::accept_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2, 301),
+			Error::<Test>::BidPriceTooLow
+		);
+
+		// check a swap is all or nothing, the auctioned kitty can't move so neither does the other
+		assert_ok!(KittiesV2::create_auction(RuntimeOrigin::signed(BOB), KITTY_2, 100, 10));
+		assert!(KittiesV2::accept_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2, 300).is_err());
+		assert_eq!(<Kitties<Test>>::get(KITTY_1).unwrap().owner, ALICE);
+		assert_eq!(Balances::reserved_balance(ALICE), 300);
+		assert_eq!(Balances::free_balance(BOB), 10_000);
+	})
+}
+
+#[test]
+fn test_cancel_swap_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_2));
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, Some(300)));
+		assert_noop!(
+			KittiesV2::cancel_swap(RuntimeOrigin::signed(BOB), KITTY_1),
+			Error::<Test>::NotOwner
+		);
+
+		assert_ok!(KittiesV2::cancel_swap(RuntimeOrigin::signed(ALICE), KITTY_1));
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::SwapCancelled { offered: KITTY_1 }));
+		assert_eq!(<Swaps<Test>>::get(KITTY_1), None);
+		assert_eq!(<SwapRequests<Test>>::get(KITTY_2).len(), 0);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_noop!(
+			KittiesV2::cancel_swap(RuntimeOrigin::signed(ALICE), KITTY_1),
+			Error::<Test>::NoSwap
+		);
+	})
+}
+
+#[test]
+fn test_moving_kitty_invalidates_swaps() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), BOB, KITTY_2));
+
+		// check moving the requested kitty cancels the proposal
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, Some(300)));
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(BOB), CHARLIE, KITTY_2));
+		assert_eq!(<Swaps<Test>>::get(KITTY_1), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+
+		// and moving it back doesn't bring the proposal back
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(CHARLIE), BOB, KITTY_2));
+		assert_noop!(
+			KittiesV2::accept_swap(RuntimeOrigin::signed(BOB), KITTY_1, KITTY_2, 0),
+			Error::<Test>::NoSwap
+		);
+
+		// check moving the offered kitty cancels the proposal
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(ALICE), KITTY_1, KITTY_2, Some(300)));
+		assert_ok!(KittiesV2::transfer(RuntimeOrigin::signed(ALICE), DAVE, KITTY_1));
+		assert_eq!(<Swaps<Test>>::get(KITTY_1), None);
+		assert_eq!(<SwapRequests<Test>>::get(KITTY_2).len(), 0);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+
+		// check burning a kitty cancels the proposals too
+		assert_ok!(KittiesV2::propose_swap(RuntimeOrigin::signed(DAVE), KITTY_1, KITTY_2, Some(300)));
+		assert_ok!(KittiesV2::burn(RuntimeOrigin::signed(BOB), KITTY_2));
+		assert_eq!(<Swaps<Test>>::get(KITTY_1), None);
+		assert_eq!(Balances::reserved_balance(DAVE), 0);
+	})
+}
+
+#[test]
+fn test_commit_reveal_mint_work() {
+	new_test_ext().execute_with(|| {
+		let hash = BlakeTwo256::hash_of(&(ALICE, [7u8; 32]));
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(ALICE), hash));
+		assert_eq!(<MintCommits<Test>>::get(ALICE), Some(MintCommit { hash, block: 1, deposit: CommitDeposit::get() }));
+		assert_eq!(Balances::reserved_balance(ALICE), 10);
+		System::assert_last_event(RuntimeEvent::KittiesV2(Event::MintCommitted { owner: ALICE, hash }));
+
+		run_to_block(4);
+		assert_ok!(KittiesV2::reveal_mint(RuntimeOrigin::signed(ALICE), [7; 32]));
+		assert_eq!(<MintCommits<Test>>::get(ALICE), None);
+
+		// check the kitty is minted and paid by a deposit
+		let kitty = <KittiesOwned<Test>>::get(ALICE)[2];
+		System::assert_has_event(RuntimeEvent::KittiesV2(Event::Created { kitty, owner: ALICE }));
+		assert_eq!(<KittyDeposits<Test>>::get(kitty), Some(KittyDeposit::get()));
+		assert_eq!(Balances::reserved_balance(ALICE), 100);
+	})
+}
+
+#[test]
+fn test_reveal_mint_dna_depends_on_secret() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(BOB), BlakeTwo256::hash_of(&(BOB, [7u8; 32]))));
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(CHARLIE), BlakeTwo256::hash_of(&(CHARLIE, [8u8; 32]))));
+
+		// check kitties revealed in the same block get different DNA
+		run_to_block(4);
+		assert_ok!(KittiesV2::reveal_mint(RuntimeOrigin::signed(BOB), [7; 32]));
+		assert_ok!(KittiesV2::reveal_mint(RuntimeOrigin::signed(CHARLIE), [8; 32]));
+		assert_ne!(<KittiesOwned<Test>>::get(BOB)[0], <KittiesOwned<Test>>::get(CHARLIE)[0]);
+	})
+}
+
+#[test]
+fn test_commit_reveal_mint_fail() {
+	new_test_ext().execute_with(|| {
+		let hash = BlakeTwo256::hash_of(&(ALICE, [7u8; 32]));
+		assert_noop!(
+			KittiesV2::reveal_mint(RuntimeOrigin::signed(ALICE), [7; 32]),
+			Error::<Test>::NoCommit
+		);
+
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(ALICE), hash));
+		assert_noop!(
+			KittiesV2::commit_mint(RuntimeOrigin::signed(ALICE), hash),
+			Error::<Test>::CommitExists
+		);
+
+		run_to_block(3);
+		assert_noop!(
+			KittiesV2::reveal_mint(RuntimeOrigin::signed(ALICE), [7; 32]),
+			Error::<Test>::RevealTooEarly
+		);
+
+		run_to_block(4);
+		assert_noop!(
+			KittiesV2::reveal_mint(RuntimeOrigin::signed(ALICE), [8; 32]),
+			Error::<Test>::InvalidReveal
+		);
+		// another account can't reveal the secret for the committer
+		assert_noop!(
+			KittiesV2::reveal_mint(RuntimeOrigin::signed(BOB), [7; 32]),
+			Error::<Test>::NoCommit
+		);
+
+		// check the commitment is pruned once the window ends
+		run_to_block(9);
+		assert!(<MintCommits<Test>>::get(ALICE).is_some());
+		assert_eq!(Balances::reserved_balance(ALICE), 10);
+		run_to_block(10);
+		assert_eq!(<MintCommits<Test>>::get(ALICE), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(<CommitsDue<Test>>::get(4).len(), 0);
+		assert_eq!(<MintSeeds<Test>>::get(4), None);
+		assert_noop!(
+			KittiesV2::reveal_mint(RuntimeOrigin::signed(ALICE), [7; 32]),
+			Error::<Test>::NoCommit
+		);
+
+		// check an expired commitment may be replaced
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(ALICE), hash));
+		assert_eq!(<MintCommits<Test>>::get(ALICE).unwrap().block, 10);
+	})
+}
+
+#[test]
+fn test_reveal_mint_uses_due_block_randomness() {
+	use codec::Encode;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(ALICE), BlakeTwo256::hash_of(&(ALICE, [7u8; 32]))));
+		run_to_block(4);
+		let seed = <MintSeeds<Test>>::get(4).unwrap();
+
+		// check waiting for other randomness within the window doesn't change the DNA
+		RandomSeed::set(H256::repeat_byte(9));
+		run_to_block(8);
+		assert_ok!(KittiesV2::reveal_mint(RuntimeOrigin::signed(ALICE), [7; 32]));
+		let kitty = sp_io::hashing::blake2_128(&(seed, [7u8; 32], ALICE).encode());
+		assert_eq!(<KittiesOwned<Test>>::get(ALICE)[2], kitty);
+		assert_eq!(<CommitsDue<Test>>::get(4).len(), 0);
+	})
+}
+
+#[test]
+fn test_commit_mint_too_many_fail() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(ALICE), H256::repeat_byte(1)));
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(BOB), H256::repeat_byte(2)));
+		assert_noop!(
+			KittiesV2::commit_mint(RuntimeOrigin::signed(CHARLIE), H256::repeat_byte(3)),
+			Error::<Test>::TooManyCommits
+		);
+
+		// check commitments due in another block are fine
+		run_to_block(2);
+		assert_ok!(KittiesV2::commit_mint(RuntimeOrigin::signed(CHARLIE), H256::repeat_byte(3)));
+	})
+}
@@ -0,0 +1,230 @@
+//! Kitty genetics.
+//!
+//! A kitty DNA is read as a genome of 16 loci, one byte each:
+//!
+//! | Locus   | Gene                                    |
+//! |---------|-----------------------------------------|
+//! | 0       | gender, an even byte is a male          |
+//! | 1, 2    | the two alleles of the color gene       |
+//! | 3, 4    | the two alleles of the pattern gene     |
+//! | 5, 6    | the two alleles of the eye shape gene   |
+//! | 7, 8    | the two alleles of the rarity gene      |
+//! | 9..16   | not expressed yet, inherited as is      |
+//!
+//! Every gene carries one allele from each parent. The variants of a trait are declared from the
+//! most dominant to the most recessive, so a kitty expresses the more dominant of its two alleles
+//! and a recessive trait only shows when both alleles carry it.
+//!
+//! Breeding may also mutate loci, replacing the inherited byte with a new one so alleles that
+//! neither parent carries can appear.
+
+use crate::{Dna, Gender};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_io::hashing::blake2_128;
+use sp_runtime::{traits::Zero, Perbill, RuntimeDebug};
+use sp_std::vec::Vec;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// The locus of the first allele of the color gene.
+pub const COLOR_LOCUS: usize = 1;
+/// The locus of the first allele of the pattern gene.
+pub const PATTERN_LOCUS: usize = 3;
+/// The locus of the first allele of the eye shape gene.
+pub const EYE_SHAPE_LOCUS: usize = 5;
+/// The locus of the first allele of the rarity gene.
+pub const RARITY_LOCUS: usize = 7;
+
+// The loci holding an allele of some gene, the other loci are inherited from either parent
+const GENE_LOCI: [usize; 4] = [COLOR_LOCUS, PATTERN_LOCUS, EYE_SHAPE_LOCUS, RARITY_LOCUS];
+
+/// A trait variant encoded by an allele, ordered from the most dominant to the most recessive.
+pub trait Allele: Copy + Ord {
+	/// The variant an allele byte encodes.
+	fn from_byte(byte: u8) -> Self;
+}
+
+/// Coat color, black is dominant and white recessive.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Color {
+	Black,
+	Ginger,
+	Grey,
+	Cream,
+	White,
+}
+
+impl Allele for Color {
+	fn from_byte(byte: u8) -> Self {
+		match byte % 5 {
+			0 => Color::Black,
+			1 => Color::Ginger,
+			2 => Color::Grey,
+			3 => Color::Cream,
+			_ => Color::White,
+		}
+	}
+}
+
+/// Coat pattern, tabby is dominant and colorpoint recessive.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Pattern {
+	Tabby,
+	Solid,
+	Spotted,
+	Colorpoint,
+}
+
+impl Allele for Pattern {
+	fn from_byte(byte: u8) -> Self {
+		match byte % 4 {
+			0 => Pattern::Tabby,
+			1 => Pattern::Solid,
+			2 => Pattern::Spotted,
+			_ => Pattern::Colorpoint,
+		}
+	}
+}
+
+/// Eye shape, round is dominant and crescent recessive.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum EyeShape {
+	Round,
+	Almond,
+	Slanted,
+	Crescent,
+}
+
+impl Allele for EyeShape {
+	fn from_byte(byte: u8) -> Self {
+		match byte % 4 {
+			0 => EyeShape::Round,
+			1 => EyeShape::Almond,
+			2 => EyeShape::Slanted,
+			_ => EyeShape::Crescent,
+		}
+	}
+}
+
+/// Rarity tier, the rarer tiers are both less likely alleles and recessive.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Rarity {
+	Common,
+	Uncommon,
+	Rare,
+	Legendary,
+}
+
+impl Allele for Rarity {
+	fn from_byte(byte: u8) -> Self {
+		match byte {
+			0..=159 => Rarity::Common,
+			160..=223 => Rarity::Uncommon,
+			224..=251 => Rarity::Rare,
+			_ => Rarity::Legendary,
+		}
+	}
+}
+
+/// The two alleles of a gene and the trait they express.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Gene<A> {
+	pub alleles: (A, A),
+	pub expressed: A,
+}
+
+impl<A: Allele> Gene<A> {
+	/// Reads the gene whose first allele is at `locus`.
+	pub fn decode(dna: &Dna, locus: usize) -> Self {
+		let alleles = (A::from_byte(dna[locus]), A::from_byte(dna[locus + 1]));
+		Gene { alleles, expressed: alleles.0.min(alleles.1) }
+	}
+
+	/// Whether the kitty carries a trait it doesn't express.
+	pub fn is_carrier(&self) -> bool {
+		self.alleles.0 != self.alleles.1
+	}
+}
+
+/// The traits decoded from a kitty DNA.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Traits {
+	pub gender: Gender,
+	pub color: Gene<Color>,
+	pub pattern: Gene<Pattern>,
+	pub eye_shape: Gene<EyeShape>,
+	pub rarity: Gene<Rarity>,
+}
+
+/// The gender encoded in a DNA.
+pub fn gender(dna: &Dna) -> Gender {
+	if dna[0] % 2 == 0 {
+		// Males are identified by having an even leading byte
+		Gender::Male
+	} else {
+		// Females are identified by having an odd leading byte
+		Gender::Female
+	}
+}
+
+/// Decodes the traits of a DNA.
+pub fn decode(dna: &Dna) -> Traits {
+	Traits {
+		gender: gender(dna),
+		color: Gene::decode(dna, COLOR_LOCUS),
+		pattern: Gene::decode(dna, PATTERN_LOCUS),
+		eye_shape: Gene::decode(dna, EYE_SHAPE_LOCUS),
+		rarity: Gene::decode(dna, RARITY_LOCUS),
+	}
+}
+
+/// The DNA of a child of two kitties.
+///
+/// Each gene gets one of the two alleles of the first parent and one of the two alleles of the
+/// second parent, the other loci come from either parent. Every choice is made by the lowest bit of
+/// the `random` byte at the same locus, and the gender locus is taken from `random` as is.
+pub fn inherit(parent_1: &Dna, parent_2: &Dna, random: &Dna) -> Dna {
+	let pick = |locus: usize, a: u8, b: u8| if random[locus] % 2 == 0 { a } else { b };
+
+	let mut dna = Dna::default();
+	dna[0] = random[0];
+	for locus in 1..dna.len() {
+		dna[locus] = pick(locus, parent_1[locus], parent_2[locus]);
+	}
+	for locus in GENE_LOCI {
+		dna[locus] = pick(locus, parent_1[locus], parent_1[locus + 1]);
+		dna[locus + 1] = pick(locus + 1, parent_2[locus], parent_2[locus + 1]);
+	}
+	dna
+}
+
+/// Randomly mutates the loci of a DNA, returning the mutated loci.
+///
+/// Each locus but the gender one, which is random already, mutates with probability `rate`. A
+/// mutated locus always ends up with a different byte. The rolls are derived from `random`, so the
+/// same inputs always mutate the same loci.
+pub fn mutate(dna: &mut Dna, rate: Perbill, random: &Dna) -> Vec<u8> {
+	let mut mutated = Vec::new();
+	if rate.is_zero() {
+		return mutated
+	}
+
+	for locus in 1..dna.len() {
+		let roll = blake2_128(&(random, b"mutation", locus as u8).encode());
+		let chance = u32::from_le_bytes([roll[0], roll[1], roll[2], roll[3]]) % 1_000_000_000;
+		if Perbill::from_parts(chance) < rate {
+			// shift by 1..=255 so the byte can't stay the same
+			dna[locus] = dna[locus].wrapping_add(1 + roll[4] % 255);
+			mutated.push(locus as u8);
+		}
+	}
+	mutated
+}
@@ -0,0 +1,33 @@
+//! Types and logic shared by the kitties pallets.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+pub mod genetics;
+pub mod ownership;
+
+/// The DNA of a kitty, which also identifies it.
+pub type Dna = [u8; 16];
+
+/// The gender of a kitty.
+#[derive(Clone, Encode, Decode, PartialEq, Copy, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Gender {
+	Female,
+	Male,
+}
+
+/// The DNA of a kitty identified by a hash, as the older kitty pallets do. Keeps the leading bytes
+/// of the hash.
+pub fn dna_from_hash(hash: &[u8]) -> Dna {
+	let mut dna = Dna::default();
+	let len = hash.len().min(dna.len());
+	dna[..len].copy_from_slice(&hash[..len]);
+	dna
+}
@@ -0,0 +1,23 @@
+//! The index of the kitties each account owns.
+//!
+//! Pallets keep the index as a bounded list of kitty ids per owner, so an account can't own more
+//! kitties than the bound.
+
+use sp_runtime::{traits::Get, BoundedVec};
+
+/// Adds a kitty to the kitties of an owner, giving it back if the owner can't own any more.
+pub fn insert<K, S: Get<u32>>(owned: &mut BoundedVec<K, S>, kitty: K) -> Result<(), K> {
+	owned.try_push(kitty)
+}
+
+/// Removes a kitty from the kitties of an owner, returning whether the owner had it. The order of
+/// the remaining kitties is not kept.
+pub fn remove<K: PartialEq, S>(owned: &mut BoundedVec<K, S>, kitty: &K) -> bool {
+	match owned.iter().position(|id| id == kitty) {
+		Some(index) => {
+			owned.swap_remove(index);
+			true
+		},
+		None => false,
+	}
+}
@@ -10,12 +10,18 @@ use pallet_grandpa::{
 	fg_primitives, AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList,
 };
 use sp_api::impl_runtime_apis;
+#[cfg(not(feature = "babe"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use frame_support::{
+	traits::{Currency, OnUnbalanced},
+	PalletId,
+};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount,
+		NumberFor, One, Verify,
 	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
@@ -29,7 +35,7 @@ use sp_version::RuntimeVersion;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo, Time
+		ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo, Time
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -91,12 +97,21 @@ pub mod opaque {
 	/// Opaque block identifier type.
 	pub type BlockId = generic::BlockId<Block>;
 
+	#[cfg(not(feature = "babe"))]
 	impl_opaque_keys! {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
 		}
 	}
+
+	#[cfg(feature = "babe")]
+	impl_opaque_keys! {
+		pub struct SessionKeys {
+			pub babe: Babe,
+			pub grandpa: Grandpa,
+		}
+	}
 }
 
 // To learn more about runtime versioning, see:
@@ -111,7 +126,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 103,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -135,6 +150,23 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+/// The length of a BABE epoch. Kitty DNA uses the randomness of the epoch before the current
+/// one, so short epochs keep reveals quick.
+#[cfg(feature = "babe")]
+pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = 10 * MINUTES;
+
+/// 1 in 4 blocks (on average, not counting collisions) will be primary BABE blocks.
+#[cfg(feature = "babe")]
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
+/// The BABE epoch configuration at genesis.
+#[cfg(feature = "babe")]
+pub const BABE_GENESIS_EPOCH_CONFIG: sp_consensus_babe::BabeEpochConfiguration =
+	sp_consensus_babe::BabeEpochConfiguration {
+		c: PRIMARY_PROBABILITY,
+		allowed_slots: sp_consensus_babe::AllowedSlots::PrimaryAndSecondaryPlainSlots,
+	};
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -149,6 +181,18 @@ parameter_types! {
 	pub const MaxAddend: u32 = 20;
 	pub const ClearFrequency: u32 = 10;
 	pub const MaxKittyOwned: u32 = 9999;
+	pub const MaxAuctionsPerBlock: u32 = 100;
+	pub const MaxOffersPerKitty: u32 = 50;
+	pub const KittyBreedCooldown: BlockNumber = 10 * MINUTES;
+	pub const MaxKittyChildren: u32 = 100;
+	pub const KittyMarketplaceFee: Perbill = Perbill::from_percent(2);
+	pub const KittyCreatorRoyalty: Perbill = Perbill::from_percent(5);
+	pub const KittyMutationRate: Perbill = Perbill::from_percent(1);
+	pub const KittyDeposit: Balance = 10 * EXISTENTIAL_DEPOSIT;
+	pub const KittyMarketplacePalletId: PalletId = PalletId(*b"kt/mrket");
+	pub const KittenAge: u64 = 7 * DAYS as u64 * MILLISECS_PER_BLOCK;
+	pub const KittyElderAge: u64 = 365 * DAYS as u64 * MILLISECS_PER_BLOCK;
+	pub const KittyBasePrice: Balance = 100 * EXISTENTIAL_DEPOSIT;
 	/// We allow for 2 seconds of compute with a 6 second average block time.
 	pub BlockWeights: frame_system::limits::BlockWeights =
 		frame_system::limits::BlockWeights::with_sensible_defaults(
@@ -217,12 +261,65 @@ impl frame_system::Config for Runtime {
 
 impl pallet_randomness_collective_flip::Config for Runtime {}
 
+#[cfg(not(feature = "babe"))]
 impl pallet_aura::Config for Runtime {
 	type AuthorityId = AuraId;
 	type DisabledValidators = ();
 	type MaxAuthorities = ConstU32<32>;
 }
 
+#[cfg(feature = "babe")]
+parameter_types! {
+	pub const EpochDuration: u64 = EPOCH_DURATION_IN_BLOCKS as u64;
+	pub const ExpectedBlockTime: u64 = MILLISECS_PER_BLOCK;
+}
+
+#[cfg(feature = "babe")]
+impl pallet_babe::Config for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	// There is no session pallet, the genesis authorities stay
+	type EpochChangeTrigger = pallet_babe::SameAuthoritiesForever;
+	type DisabledValidators = ();
+
+	type KeyOwnerProofSystem = ();
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = ();
+
+	type WeightInfo = ();
+	type MaxAuthorities = ConstU32<32>;
+}
+
+/// The randomness kitty DNA is drawn from. BABE VRF outputs can't be biased by block authors
+/// like the block hashes mixed by RandomnessCollectiveFlip.
+#[cfg(not(feature = "babe"))]
+pub type KittyRandomnessSource = RandomnessCollectiveFlip;
+#[cfg(feature = "babe")]
+pub type KittyRandomnessSource = pallet_babe::RandomnessFromOneEpochAgo<Runtime>;
+
+#[cfg(not(feature = "babe"))]
+parameter_types! {
+	// Outlasts the 81 block hashes RandomnessCollectiveFlip mixes
+	pub const KittyRevealDelay: BlockNumber = 10 * MINUTES;
+}
+
+#[cfg(feature = "babe")]
+parameter_types! {
+	// The randomness of one epoch ago is known since the start of the epoch before, two epochs
+	// after a commitment it is always fresh
+	pub const KittyRevealDelay: BlockNumber = 2 * EPOCH_DURATION_IN_BLOCKS;
+}
+
 impl pallet_grandpa::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 
@@ -245,7 +342,10 @@ impl pallet_grandpa::Config for Runtime {
 impl pallet_timestamp::Config for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
+	#[cfg(not(feature = "babe"))]
 	type OnTimestampSet = Aura;
+	#[cfg(feature = "babe")]
+	type OnTimestampSet = Babe;
 	type MinimumPeriod = ConstU64<{ SLOT_DURATION / 2 }>;
 	type WeightInfo = ();
 }
@@ -361,23 +461,63 @@ impl pallet_lockable_currency::Config for Runtime {
 impl pallet_kitties::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
-	type KittyRandomness = RandomnessCollectiveFlip;
+	type KittyRandomness = KittyRandomnessSource;
 	type MaxKittyOwned = MaxKittyOwned;
 }
 
 impl pallet_kitties_v2::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
-	type KittyRandomness = RandomnessCollectiveFlip;
+	type KittyRandomness = KittyRandomnessSource;
 	type MaxKittiesOwned = MaxKittyOwned;
+	type MaxAuctionsPerBlock = MaxAuctionsPerBlock;
+	type MaxOffersPerKitty = MaxOffersPerKitty;
+	type BreedCooldown = KittyBreedCooldown;
+	type MaxChildren = MaxKittyChildren;
+	type MaxLineageDepth = ConstU32<8>;
+	type InbreedingCheckDepth = ConstU32<2>;
+	type MaxRentalsPerBlock = ConstU32<100>;
+	type MarketplaceFee = KittyMarketplaceFee;
+	type FeeDestination = KittyMarketplaceFees;
+	type CreatorRoyalty = KittyCreatorRoyalty;
+	type MutationRate = KittyMutationRate;
+	type KittyDeposit = KittyDeposit;
+	type MaxBatchSize = ConstU32<50>;
+	// Kitty names are reserved like nicks in pallet_nicks
+	type MetadataDeposit = ConstU128<100>;
+	type Slashed = ();
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type MinNameLength = ConstU32<3>;
+	type MaxNameLength = ConstU32<32>;
+	type MaxUriLength = ConstU32<128>;
+	type ClearMetadataOnTransfer = ConstBool<true>;
+	type MaxSwapsPerKitty = ConstU32<50>;
+	type RevealDelay = KittyRevealDelay;
+	type RevealWindow = ConstU32<{ 10 * MINUTES }>;
+	type MaxCommitsPerBlock = ConstU32<100>;
+	type CommitDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
+}
+
+/// Collects the kitty marketplace fees in the marketplace account.
+pub struct KittyMarketplaceFees;
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Runtime>> for KittyMarketplaceFees {
+	fn on_nonzero_unbalanced(amount: pallet_balances::NegativeImbalance<Runtime>) {
+		let account: AccountId = KittyMarketplacePalletId::get().into_account_truncating();
+		Balances::resolve_creating(&account, amount);
+	}
 }
 
 impl pallet_kitties_myself::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Time = Timestamp;
 	type MaxKittiesOwned = MaxKittyOwned;
-	type KittyDnaRandom = RandomnessCollectiveFlip;
+	type KittyDnaRandom = KittyRandomnessSource;
 	type Currency = Balances;
+	type MaxDnaAttempts = ConstU32<10>;
+	type KittenAge = KittenAge;
+	type ElderAge = KittyElderAge;
+	type ExpectedBlockTime = ConstU64<MILLISECS_PER_BLOCK>;
+	type BasePrice = KittyBasePrice;
 }
 
 impl pallet_tight_coupling::Config for Runtime {
@@ -389,6 +529,7 @@ impl pallet_loose_coupling::Config for Runtime {
 	type IncreaseValue = TemplateModule;
 }
 // Create the runtime by composing the FRAME pallets that were previously configured.
+#[cfg(not(feature = "babe"))]
 construct_runtime!( // tạo ra một runtime từ danh sách các pallet mà mình impl
 	pub struct Runtime
 	where
@@ -419,6 +560,39 @@ construct_runtime!( // tạo ra một runtime từ danh sách các pallet mà m
 	}
 );
 
+// The same runtime with BABE in place of Aura. `construct_runtime!` doesn't take `#[cfg]` on a
+// single pallet, so the pallets are listed again.
+#[cfg(feature = "babe")]
+construct_runtime!( // tạo ra một runtime từ danh sách các pallet mà mình impl
+	pub struct Runtime
+	where
+		Block = Block,
+		NodeBlock = opaque::Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		RandomnessCollectiveFlip: pallet_randomness_collective_flip,
+		Babe: pallet_babe,
+		Timestamp: pallet_timestamp,
+		Grandpa: pallet_grandpa,
+		Balances: pallet_balances,
+		TransactionPayment: pallet_transaction_payment,
+		Sudo: pallet_sudo,
+		Utility: pallet_utility,
+		// Include the custom logic from the pallet-template in the runtime.
+		TemplateModule: pallet_template,
+		Something: pallet_something,
+		MintToken: pallet_mint_token,
+		LockableCurrency: pallet_lockable_currency,
+		Kitties: pallet_kitties,
+		KittiesV2: pallet_kitties_v2,
+		KittiesMyself: pallet_kitties_myself,
+		TightCoupling: pallet_tight_coupling,
+		LooseCoupling: pallet_loose_coupling,
+		Nicks: pallet_nicks,
+	}
+);
+
 /// The address format for describing accounts.
 pub type Address = sp_runtime::MultiAddress<AccountId, ()>;
 /// Block header type as expected by this runtime.
@@ -442,6 +616,25 @@ pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
+parameter_types! {
+	pub const KittiesPalletName: &'static str = "Kitties";
+	pub const KittiesMyselfPalletName: &'static str = "KittiesMyself";
+}
+
+/// Storage migrations executed on runtime upgrade.
+pub type Migrations = (
+	pallet_kitties_v2::migrations::v1::MigrateToV1<Runtime>,
+	pallet_kitties_v2::migrations::v2::MigrateToV2<Runtime>,
+	pallet_kitties_myself::migrations::v1::MigrateToV1<Runtime>,
+	// Gather the kitties of the older kitty pallets in KittiesV2. This runs once, gated on the
+	// KittiesV2 storage version, drop it from this list after the upgrade that enacts it.
+	pallet_kitties_v2::migrations::unify::MigrateToV3<
+		Runtime,
+		KittiesPalletName,
+		KittiesMyselfPalletName,
+		u64,
+	>,
+);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -449,6 +642,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -524,6 +718,7 @@ impl_runtime_apis! { // impl các interface runtime API
 		}
 	}
 
+	#[cfg(not(feature = "babe"))]
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime { // aura consensus => authorize block (aura, babe => authorize, grandpa: finalize)
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
 			sp_consensus_aura::SlotDuration::from_millis(Aura::slot_duration())
@@ -534,6 +729,47 @@ impl_runtime_apis! { // impl các interface runtime API
 		}
 	}
 
+	#[cfg(feature = "babe")]
+	impl sp_consensus_babe::BabeApi<Block> for Runtime { // babe consensus => authorize block, its VRF outputs seed the kitty DNA
+		fn configuration() -> sp_consensus_babe::BabeConfiguration {
+			let epoch_config = Babe::epoch_config().unwrap_or(BABE_GENESIS_EPOCH_CONFIG);
+			sp_consensus_babe::BabeConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EpochDuration::get(),
+				c: epoch_config.c,
+				authorities: Babe::authorities().to_vec(),
+				randomness: Babe::randomness(),
+				allowed_slots: epoch_config.allowed_slots,
+			}
+		}
+
+		fn current_epoch_start() -> sp_consensus_babe::Slot {
+			Babe::current_epoch_start()
+		}
+
+		fn current_epoch() -> sp_consensus_babe::Epoch {
+			Babe::current_epoch()
+		}
+
+		fn next_epoch() -> sp_consensus_babe::Epoch {
+			Babe::next_epoch()
+		}
+
+		fn generate_key_ownership_proof(
+			_slot: sp_consensus_babe::Slot,
+			_authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			_key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime { // create and decode session key
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			opaque::SessionKeys::generate(seed)
@@ -614,6 +850,58 @@ impl_runtime_apis! { // impl các interface runtime API
 		}
 	}
 
+	impl pallet_kitties_v2_runtime_api::KittiesApi<Block, AccountId, Balance, BlockNumber> for Runtime { // truy vấn kitty
+		fn kitties_by_owner(owner: AccountId) -> Vec<[u8; 16]> {
+			KittiesV2::kitties_by_owner(&owner)
+		}
+
+		fn kitty(kitty: [u8; 16]) -> Option<pallet_kitties_v2::KittyDetails<AccountId, Balance, BlockNumber>> {
+			KittiesV2::kitty_details(kitty)
+		}
+
+		fn kitties_for_sale(start: Option<(Balance, [u8; 16])>, limit: u32) -> Vec<([u8; 16], Balance)> {
+			KittiesV2::kitties_for_sale(start, limit)
+		}
+
+		fn kitty_count() -> u64 {
+			KittiesV2::kitty_count()
+		}
+	}
+
+	impl pallet_kitties_v2_runtime_api::KittiesLineageApi<Block> for Runtime { // truy vấn phả hệ kitty
+		fn ancestry(kitty: [u8; 16], depth: u32) -> Vec<pallet_kitties_v2::AncestorNode> {
+			KittiesV2::ancestry(kitty, depth)
+		}
+
+		fn children(kitty: [u8; 16]) -> Vec<[u8; 16]> {
+			KittiesV2::children(kitty)
+		}
+
+		fn shared_ancestors(kitty_1: [u8; 16], kitty_2: [u8; 16], depth: u32) -> Vec<[u8; 16]> {
+			KittiesV2::shared_ancestors(kitty_1, kitty_2, depth)
+		}
+	}
+
+	impl pallet_kitties_v2_runtime_api::KittiesGeneticsApi<Block> for Runtime { // giải mã gen kitty
+		fn traits(kitty: [u8; 16]) -> Option<pallet_kitties_v2::genetics::Traits> {
+			KittiesV2::traits(kitty)
+		}
+
+		fn decode_dna(dna: [u8; 16]) -> pallet_kitties_v2::genetics::Traits {
+			pallet_kitties_v2::genetics::decode(&dna)
+		}
+	}
+
+	impl pallet_kitties_myself_runtime_api::KittiesAgeApi<Block, Hash, BlockNumber, u64, Balance> for Runtime { // tuổi kitty
+		fn age(kitty: Hash) -> Option<pallet_kitties_myself::KittyAge<BlockNumber, u64>> {
+			KittiesMyself::kitty_age(kitty)
+		}
+
+		fn suggested_price(kitty: Hash) -> Option<Balance> {
+			KittiesMyself::suggested_price(kitty)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime { // ước tính và đo lường time vận hành cần thiết để hoàn tất giao dịch
 		fn benchmark_metadata(extra: bool) -> (